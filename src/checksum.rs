@@ -0,0 +1,258 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use serde::de::{DeserializeOwned, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The unit struct name `ChecksumStart` is serialized under.
+pub(crate) const CHECKSUM_START: &str = "\0bitserde::ChecksumStart";
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Crc8 {}
+    impl Sealed for super::Crc16 {}
+    impl Sealed for super::Crc32 {}
+    impl Sealed for super::Parity {}
+    impl Sealed for super::Sum8 {}
+}
+
+/// A checksum over a run of bits, fed one bit after the other in stream order so the run does not
+/// have to start or end on a byte boundary.
+///
+/// `BitSerializer` and `BitDeserializer` recognise the algorithms of this module by `NAME`, so the
+/// trait is sealed: an algorithm they did not know would silently go unchecked.
+pub trait Checksum: sealed::Sealed {
+    /// The checksum as it is written, like any other value of its type.
+    type Value: Copy + Default + Debug + PartialEq + Into<u64> + Serialize + DeserializeOwned;
+
+    /// The newtype struct name `Crc<Self>` is serialized under.
+    const NAME: &'static str;
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> Self::Value
+    where
+        BitSlice<O, T>: BitField;
+}
+
+/// Runs a CRC bit by bit, shifting towards the most significant bit or, if `reflected`, towards
+/// the least significant bit with `poly` given reflected.
+fn crc<O: BitOrder, T: BitStore>(
+    bits: &BitSlice<O, T>,
+    width: u32,
+    poly: u64,
+    init: u64,
+    reflected: bool,
+) -> u64 {
+    let mask = u64::MAX >> (64 - width);
+    let top = 1 << (width - 1);
+    bits.iter().fold(init, |crc, bit| {
+        if reflected {
+            let feedback = (crc & 1 == 1) ^ *bit;
+            crc >> 1 ^ if feedback { poly } else { 0 }
+        } else {
+            let feedback = (crc & top != 0) ^ *bit;
+            (crc << 1 & mask) ^ if feedback { poly } else { 0 }
+        }
+    })
+}
+
+/// CRC-8/SMBUS, polynomial 0x07. Matches the usual check values for byte aligned data in a
+/// `Msb0` stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Crc8;
+
+/// CRC-16/IBM-3740, also known as CRC-16/CCITT-FALSE, polynomial 0x1021 starting from 0xFFFF.
+/// Matches the usual check values for byte aligned data in a `Msb0` stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Crc16;
+
+/// CRC-32/ISO-HDLC as used by Ethernet, zlib and PNG. It is reflected, so it matches the usual
+/// check values for byte aligned data in a `Lsb0` stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Crc32;
+
+/// A single even parity bit, set when the bits hold an odd number of ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Parity;
+
+/// The wrapping sum of the bits taken eight at a time, each group read like a `u8`. The last group
+/// may be shorter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Sum8;
+
+impl Checksum for Crc8 {
+    type Value = u8;
+    const NAME: &'static str = "\0bitserde::Crc8";
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> u8
+    where
+        BitSlice<O, T>: BitField,
+    {
+        crc(bits, 8, 0x07, 0, false) as u8
+    }
+}
+
+impl Checksum for Crc16 {
+    type Value = u16;
+    const NAME: &'static str = "\0bitserde::Crc16";
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> u16
+    where
+        BitSlice<O, T>: BitField,
+    {
+        crc(bits, 16, 0x1021, 0xFFFF, false) as u16
+    }
+}
+
+impl Checksum for Crc32 {
+    type Value = u32;
+    const NAME: &'static str = "\0bitserde::Crc32";
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> u32
+    where
+        BitSlice<O, T>: BitField,
+    {
+        !(crc(bits, 32, 0xEDB8_8320, 0xFFFF_FFFF, true) as u32)
+    }
+}
+
+impl Checksum for Parity {
+    type Value = bool;
+    const NAME: &'static str = "\0bitserde::Parity";
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> bool
+    where
+        BitSlice<O, T>: BitField,
+    {
+        bits.count_ones() % 2 == 1
+    }
+}
+
+impl Checksum for Sum8 {
+    type Value = u8;
+    const NAME: &'static str = "\0bitserde::Sum8";
+
+    fn compute<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> u8
+    where
+        BitSlice<O, T>: BitField,
+    {
+        bits.chunks(8)
+            .fold(0u8, |sum, group| sum.wrapping_add(group.load_le::<u8>()))
+    }
+}
+
+/// Marks where the bits covered by the following `Crc` fields start. Without one a `Crc` covers
+/// everything from the start of the stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ChecksumStart;
+
+impl Serialize for ChecksumStart {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_unit_struct(CHECKSUM_START)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChecksumStart {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StartVisitor;
+
+        impl<'de> Visitor<'de> for StartVisitor {
+            type Value = ChecksumStart;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a checksum start marker")
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(ChecksumStart)
+            }
+        }
+
+        deserializer.deserialize_unit_struct(CHECKSUM_START, StartVisitor)
+    }
+}
+
+/// A checksum `A` over the bits since the last `ChecksumStart`.
+///
+/// `BitSerializer` computes the checksum and writes it in place of the wrapped value,
+/// `BitDeserializer` recomputes it and fails with `Error::ChecksumMismatch` when the stored one
+/// differs.
+pub struct Crc<A: Checksum>(pub A::Value);
+
+impl<A: Checksum> Crc<A> {
+    pub fn new() -> Self {
+        Crc(A::Value::default())
+    }
+}
+
+impl<A: Checksum> Default for Crc<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Checksum> Clone for Crc<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A: Checksum> Copy for Crc<A> {}
+
+impl<A: Checksum> Debug for Crc<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Crc").field(&self.0).finish()
+    }
+}
+
+/// Compares the stored checksums.
+impl<A: Checksum> PartialEq for Crc<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<A: Checksum> Serialize for Crc<A> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(A::NAME, &self.0)
+    }
+}
+
+struct CrcVisitor<A>(PhantomData<A>);
+
+impl<'de, A: Checksum> Visitor<'de> for CrcVisitor<A> {
+    type Value = Crc<A>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a checksum")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        A::Value::deserialize(deserializer).map(Crc)
+    }
+}
+
+impl<'de, A: Checksum> Deserialize<'de> for Crc<A> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(A::NAME, CrcVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The newtype name `Const` is serialized under is this prefix followed by its value in 16 hex
+/// digits.
+pub(crate) const CONST: &str = "\0bitserde::Const::";
+
+const NAME_LEN: usize = CONST.len() + 16;
+
+/// Builds the newtype name carrying `value`.
+const fn const_name(value: u64) -> [u8; NAME_LEN] {
+    let mut name = [0u8; NAME_LEN];
+    let prefix = CONST.as_bytes();
+    let mut index = 0;
+    while index < prefix.len() {
+        name[index] = prefix[index];
+        index += 1;
+    }
+    while index < NAME_LEN {
+        let digit = value >> ((NAME_LEN - 1 - index) * 4) & 0xF;
+        name[index] = b"0123456789abcdef"[digit as usize];
+        index += 1;
+    }
+    name
+}
+
+/// Reads the value back from a newtype name built by `const_name`.
+pub(crate) fn const_value(name: &str) -> Option<u64> {
+    u64::from_str_radix(name.strip_prefix(CONST)?, 16).ok()
+}
+
+/// A field that always holds `VALUE`, such as a sync word or file magic.
+///
+/// `VALUE` must fit in the width of `T`, which is checked at compile time. Signed types take it as
+/// a two's complement bit pattern, so `Const<i8, 0xFF>` holds -1. Deserializing different bits
+/// fails, with `Error::ConstMismatch` in `BitDeserializer`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Const<T, const VALUE: u64>(PhantomData<T>);
+
+impl<T, const VALUE: u64> Const<T, VALUE> {
+    const NAME: &'static str = match std::str::from_utf8(&const_name(VALUE)) {
+        Ok(name) => name,
+        Err(_) => panic!("The name of a Const is not UTF-8."),
+    };
+
+    pub fn new() -> Self {
+        Const(PhantomData)
+    }
+}
+
+struct ConstVisitor<T, const VALUE: u64>(PhantomData<T>);
+
+macro_rules! impl_const {
+    ($($type:ty => $unsigned:ty),*) => {
+        $(
+            impl<const VALUE: u64> Const<$type, VALUE> {
+                const FITS: () = assert!(
+                    VALUE <= <$unsigned>::MAX as u64,
+                    "The value of a Const does not fit in its type."
+                );
+            }
+
+            impl<const VALUE: u64> Serialize for Const<$type, VALUE> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    let () = Self::FITS;
+                    serializer.serialize_newtype_struct(Self::NAME, &(VALUE as $type))
+                }
+            }
+
+            impl<'de, const VALUE: u64> Visitor<'de> for ConstVisitor<$type, VALUE> {
+                type Value = Const<$type, VALUE>;
+
+                fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                    write!(formatter, "the constant {:#x}", VALUE as $type)
+                }
+
+                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let value = <$type>::deserialize(deserializer)?;
+                    if value == VALUE as $type {
+                        return Ok(Const::new());
+                    }
+                    Err(serde::de::Error::custom(format!(
+                        "Expected the constant {:#x}, found {:#x}.",
+                        VALUE,
+                        value as $unsigned
+                    )))
+                }
+            }
+
+            impl<'de, const VALUE: u64> Deserialize<'de> for Const<$type, VALUE> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let () = Self::FITS;
+                    let visitor = ConstVisitor::<$type, VALUE>(PhantomData);
+                    deserializer.deserialize_newtype_struct(Self::NAME, visitor)
+                }
+            }
+        )*
+    };
+}
+
+impl_const![
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, i8 => u8, i16 => u16, i32 => u32, i64 => u64
+];
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::marker::PhantomData;
 
 use bitvec::field::BitField;
@@ -7,7 +8,7 @@ use bitvec::store::BitStore;
 use bitvec::vec::BitVec;
 use serde::de::{Error, SeqAccess, Visitor};
 use serde::export::Formatter;
-use serde::ser::SerializeSeq;
+use serde::ser::{SerializeSeq, SerializeTupleStruct};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Represents a bit container size.
@@ -96,3 +97,240 @@ impl<T: ContainerSize, O: BitOrder, X: BitStore> Serialize for BitContainer<T, O
         seq.end()
     }
 }
+
+/// The tuple struct name a field of a few bits is serialized under, its length carries the number
+/// of bits.
+pub(crate) const FIELD_BITS: &str = "\0bitserde::Bits";
+
+/// Serializes the low `bits` bits of `value` as a field, laid out by the encoding's `encode_bits`
+/// in `BitSerializer`.
+pub(crate) fn serialize_bits<S: Serializer>(
+    value: u64,
+    bits: usize,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if bits > 64 {
+        return Err(serde::ser::Error::custom(format!(
+            "Tried to serialize a {} bit integer, at most 64 bits are supported.",
+            bits
+        )));
+    }
+    let mut tuple = serializer.serialize_tuple_struct(FIELD_BITS, bits)?;
+    tuple.serialize_field(&value)?;
+    tuple.end()
+}
+
+struct BitsVisitor(usize);
+
+impl<'de> Visitor<'de> for BitsVisitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting {} bits", self.0)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, <A as SeqAccess<'de>>::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element()?
+            .ok_or(A::Error::custom("Couldn't grab the bits"))
+    }
+}
+
+/// Deserializes `bits` bits written by `serialize_bits`.
+pub(crate) fn deserialize_bits<'de, D: Deserializer<'de>>(
+    bits: usize,
+    deserializer: D,
+) -> Result<u64, D::Error> {
+    if bits > 64 {
+        return Err(D::Error::custom(format!(
+            "Tried to deserialize a {} bit integer, at most 64 bits are supported.",
+            bits
+        )));
+    }
+    deserializer.deserialize_tuple_struct(FIELD_BITS, bits, BitsVisitor(bits))
+}
+
+/// An unsigned integer stored in exactly `N` bits (at most 64), least significant bit first unless
+/// the encoding says otherwise, see `BinaryEncoding::encode_bits`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UInt<const N: usize>(u64);
+
+impl<const N: usize> UInt<N> {
+    /// The largest value that fits in `N` bits.
+    pub const MAX: u64 = if N >= 64 { u64::MAX } else { (1 << N) - 1 };
+
+    /// Creates the integer, failing with `Error::Overflow` if `value` does not fit in `N` bits.
+    pub fn new(value: u64) -> crate::Result<Self> {
+        if value > Self::MAX {
+            return Err(crate::Error::Overflow);
+        }
+        Ok(UInt(value))
+    }
+
+    /// The value as a native integer.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+macro_rules! impl_conversions {
+    (@impl $int:ident [$($generic:tt)*] [$($arg:tt)*], $wide:ty, $type:ty) => {
+        impl<$($generic)*> TryFrom<$type> for $int<$($arg)*> {
+            type Error = crate::Error;
+
+            fn try_from(value: $type) -> crate::Result<Self> {
+                Self::new(<$wide>::try_from(value).map_err(|_| crate::Error::Overflow)?)
+            }
+        }
+
+        impl<$($generic)*> TryFrom<$int<$($arg)*>> for $type {
+            type Error = crate::Error;
+
+            fn try_from(value: $int<$($arg)*>) -> crate::Result<Self> {
+                <$type>::try_from(value.0).map_err(|_| crate::Error::Overflow)
+            }
+        }
+    };
+    ($int:ident $generics:tt $args:tt, $wide:ty; $($type:ty),*) => {
+        $(
+            impl_conversions!(@impl $int $generics $args, $wide, $type);
+        )*
+    };
+}
+
+impl_conversions![UInt [const N: usize] [N], u64; u8, u16, u32, u64, u128, usize];
+
+impl<const N: usize> Serialize for UInt<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bits(self.0, N, serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for UInt<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_bits(N, deserializer).map(UInt)
+    }
+}
+
+/// A two's complement signed integer stored in exactly `N` bits (at most 64), laid out like `UInt`
+/// and sign extended when read back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Int<const N: usize>(i64);
+
+impl<const N: usize> Int<N> {
+    /// The smallest value that fits in `N` bits.
+    pub const MIN: i64 = match N {
+        0 => 0,
+        1..=63 => -(1 << (N - 1)),
+        _ => i64::MIN,
+    };
+
+    /// The largest value that fits in `N` bits.
+    pub const MAX: i64 = match N {
+        0 => 0,
+        1..=63 => (1 << (N - 1)) - 1,
+        _ => i64::MAX,
+    };
+
+    /// Creates the integer, failing with `Error::Overflow` if `value` does not fit in `N` bits.
+    pub fn new(value: i64) -> crate::Result<Self> {
+        if value < Self::MIN || value > Self::MAX {
+            return Err(crate::Error::Overflow);
+        }
+        Ok(Int(value))
+    }
+
+    /// The value as a native integer.
+    pub fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl_conversions![Int [const N: usize] [N], i64; i8, i16, i32, i64, i128, isize];
+
+impl<const N: usize> Serialize for Int<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bits(self.0 as u64, N, serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Int<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = deserialize_bits(N, deserializer)?;
+        Ok(Int(match N {
+            1..=63 => (value << (64 - N)) as i64 >> (64 - N),
+            _ => value as i64,
+        }))
+    }
+}
+
+/// An integer in `MIN..=MAX`, stored as `value - MIN` in the fewest bits that cover the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ranged<const MIN: i64, const MAX: i64>(i64);
+
+impl<const MIN: i64, const MAX: i64> Ranged<MIN, MAX> {
+    /// The number of bits a value takes, `ceil(log2(MAX - MIN + 1))`.
+    pub const BITS: usize = if MAX < MIN {
+        0
+    } else {
+        (64 - (MAX.wrapping_sub(MIN) as u64).leading_zeros()) as usize
+    };
+
+    /// Creates the integer, failing with `Error::Overflow` if `value` is outside `MIN..=MAX`.
+    pub fn new(value: i64) -> crate::Result<Self> {
+        if value < MIN || value > MAX {
+            return Err(crate::Error::Overflow);
+        }
+        Ok(Ranged(value))
+    }
+
+    /// The value as a native integer.
+    pub fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl_conversions![
+    Ranged [const MIN: i64, const MAX: i64] [MIN, MAX], i64;
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize
+];
+
+impl<const MIN: i64, const MAX: i64> Serialize for Ranged<MIN, MAX> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bits(self.0.wrapping_sub(MIN) as u64, Self::BITS, serializer)
+    }
+}
+
+impl<'de, const MIN: i64, const MAX: i64> Deserialize<'de> for Ranged<MIN, MAX> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let offset = deserialize_bits(Self::BITS, deserializer)?;
+        let value = MIN.wrapping_add(offset as i64);
+        if MAX < MIN || offset > MAX.wrapping_sub(MIN) as u64 {
+            return Err(D::Error::custom(format!(
+                "Decoded {} outside of {}..={}.",
+                value, MIN, MAX
+            )));
+        }
+        Ok(Ranged(value))
+    }
+}
@@ -3,16 +3,24 @@ use std::marker::PhantomData;
 use std::mem::size_of;
 
 use bitvec::field::BitField;
-use bitvec::order::{BitOrder, Lsb0};
+use bitvec::mem::BitMemory;
+use bitvec::order::{BitOrder, Lsb0, Msb0};
 use bitvec::slice::BitSlice;
 use bitvec::store::BitStore;
-use byteorder::{ReadBytesExt};
+use bitvec::vec::BitVec;
 use paste::paste;
-use serde::de::{DeserializeSeed, IntoDeserializer, SeqAccess, Visitor};
+use serde::de::value::SeqDeserializer;
+use serde::de::{DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
 use serde::Deserializer;
 
+use crate::checksum::{Checksum, Crc16, Crc32, Crc8, Parity, Sum8, CHECKSUM_START};
+use crate::constant::const_value;
+use crate::container::FIELD_BITS;
 use crate::encoding::EndianEncoding;
 use crate::error::Error::Unsupported;
+use crate::order::{BitSource, LSB_FIRST, MSB_FIRST};
+use crate::padding::{ALIGN, PADDING};
+use crate::variant::{variant_bits, COMPACT_ENUM, KNOWN_VARIANT};
 use crate::*;
 
 pub struct BitDeserializer<'de, O = Lsb0, T = usize, E = EndianEncoding>
@@ -25,25 +33,248 @@ where
     pub bits: &'de BitSlice<O, T>,
     endian: PhantomData<E>,
     pub(crate) offset: usize,
+    limit: Option<usize>,
+    max_len: Option<usize>,
+    check_padding: bool,
+    checksum_start: usize,
+    compact_variants: Option<usize>,
+    known_variant: Option<usize>,
+    constant: Option<Option<u64>>,
+    base: usize,
+    source: Option<&'de dyn BitSource>,
+    fetched: BitVec<O, T>,
 }
 
 impl<'de, O: BitOrder, S: BitStore, E: BinaryEncoding> BitDeserializer<'de, O, S, E>
 where
     BitSlice<O, S>: BitField,
 {
+    /// The bits being read, fetched from the surrounding stream inside a `Msb` or `Lsb` value.
     #[inline]
-    pub(crate) fn read_bits(&mut self, count: usize) -> &BitSlice<O, S> {
-        let slice = &self.bits[self.offset..self.offset + count];
+    fn stream(&self) -> &BitSlice<O, S> {
+        match self.source {
+            Some(_) => &self.fetched,
+            None => self.bits,
+        }
+    }
+    /// Checks that `count` more bits can be read, fetching them inside a `Msb` or `Lsb` value.
+    #[inline]
+    pub(crate) fn ensure_bits(&mut self, count: usize) -> Result<()> {
+        let end = match self.source {
+            Some(source) => source.end() - self.base,
+            None => self.bits.len(),
+        };
+        let available_bits = end - self.offset;
+        if count > available_bits {
+            return Err(Error::UnexpectedEof {
+                needed_bits: count,
+                available_bits,
+                offset: self.offset,
+            });
+        }
+        if let Some(source) = self.source {
+            let len = self.fetched.len();
+            let missing = (self.offset + count).saturating_sub(len);
+            if missing > 0 {
+                self.allocate((len + missing).div_ceil(8) - len.div_ceil(8))?;
+                let fetched = &mut self.fetched;
+                let start = self.base + len;
+                source.for_each_bit(start..start + missing, &mut |bit| fetched.push(bit));
+            }
+        }
+        Ok(())
+    }
+    #[inline]
+    pub(crate) fn read_bits(&mut self, count: usize) -> Result<&BitSlice<O, S>> {
+        self.ensure_bits(count)?;
+        let offset = self.offset;
         self.offset += count;
-        slice
+        Ok(&self.stream()[offset..offset + count])
+    }
+    /// Reads `count` bits as the least significant bits of an unsigned integer.
+    #[inline]
+    pub(crate) fn load_bits<M: BitMemory>(&mut self, count: usize) -> Result<M> {
+        if count == 0 {
+            return Ok(M::ZERO);
+        }
+        Ok(self.read_bits(count)?.load_le())
+    }
+    /// Reads `len` whole bytes.
+    #[inline]
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.ensure_bits(len.saturating_mul(8))?;
+        self.allocate(len)?;
+        let mut bytes = vec![0u8; len];
+        self.read_bits(len * 8)?.read_exact(&mut bytes[..])?;
+        Ok(bytes)
+    }
+    /// Checks a decoded length against the length limit.
+    #[inline]
+    fn check_len(&self, len: usize) -> Result<usize> {
+        match self.max_len {
+            Some(limit) if len > limit => Err(Error::LimitExceeded {
+                limit,
+                requested: len,
+            }),
+            _ => Ok(len),
+        }
+    }
+    /// Reads a sequence or map length and checks it against the length limit.
+    #[inline]
+    pub(crate) fn read_seq_len(&mut self) -> Result<usize> {
+        let len = E::deserialize_seq_len(self)?;
+        self.check_len(len)
+    }
+    /// Reads a byte buffer or string length and checks it against the length limit.
+    #[inline]
+    pub(crate) fn read_bytes_len(&mut self) -> Result<usize> {
+        let len = E::deserialize_bytes_len(self)?;
+        self.check_len(len)
+    }
+    /// Charges `bytes` against the allocation limit before they are allocated.
+    #[inline]
+    pub(crate) fn allocate(&mut self, bytes: usize) -> Result<()> {
+        if let Some(limit) = self.limit {
+            if bytes > limit {
+                return Err(Error::LimitExceeded {
+                    limit,
+                    requested: bytes,
+                });
+            }
+            self.limit = Some(limit - bytes);
+        }
+        Ok(())
     }
     pub fn new(slice: &'de BitSlice<O, S>) -> Self {
         BitDeserializer {
             bits: slice,
             endian: PhantomData,
             offset: 0,
+            limit: None,
+            max_len: None,
+            check_padding: false,
+            checksum_start: 0,
+            compact_variants: None,
+            known_variant: None,
+            constant: None,
+            base: 0,
+            source: None,
+            fetched: BitVec::new(),
         }
     }
+    /// Deserializes a value that was serialized in bit order `P`, see `serialize_in_order`.
+    ///
+    /// The nested stream starts at the last byte boundary and fetches the bits it reads from the
+    /// surrounding one as it goes, so only those are copied and charged against the allocation
+    /// limit.
+    fn deserialize_in_order<'v, P: BitOrder, V: Visitor<'v>>(
+        &mut self,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        BitSlice<P, u8>: BitField,
+    {
+        let bits = self.bits;
+        let source = self.source.unwrap_or(&bits);
+        let position = self.base + self.offset;
+        let mut inner = BitDeserializer::<P, u8, E>::new(BitSlice::empty());
+        inner.base = position - position % 8;
+        inner.offset = position % 8;
+        inner.source = Some(source);
+        inner.limit = self.limit;
+        inner.max_len = self.max_len;
+        inner.check_padding = self.check_padding;
+        inner.checksum_start = self.checksum_start;
+        inner.compact_variants = self.compact_variants.take();
+        inner.known_variant = self.known_variant.take();
+        let value = visitor.visit_newtype_struct(&mut inner);
+        let skipped = position % 8;
+        let value = value.map_err(|error| error.offset_by(self.offset - skipped))?;
+        self.offset += inner.offset - skipped;
+        self.limit = inner.limit;
+        self.checksum_start = inner.checksum_start;
+        Ok(value)
+    }
+    /// Reads the checksum `A` and compares it with the one over the bits since the last
+    /// `ChecksumStart`.
+    fn deserialize_checksum<'v, A: Checksum, V: Visitor<'v>>(
+        &mut self,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        A::Value: IntoDeserializer<'v, Error>,
+    {
+        let offset = self.offset;
+        let position = self.base + offset;
+        let start = self.checksum_start.min(position);
+        let expected = match self.source {
+            Some(source) => {
+                let mut bits = BitVec::<Lsb0, u8>::with_capacity(position - start);
+                source.for_each_bit(start..position, &mut |bit| bits.push(bit));
+                A::compute::<Lsb0, u8>(&bits)
+            }
+            None => A::compute(&self.bits[start..offset]),
+        };
+        let actual = A::Value::deserialize(&mut *self)?;
+        if actual != expected {
+            return Err(Error::ChecksumMismatch {
+                expected: expected.into(),
+                actual: actual.into(),
+                offset,
+            });
+        }
+        visitor.visit_newtype_struct(actual.into_deserializer())
+    }
+    /// Reads a `Const` and compares the integer inside with `expected`.
+    fn deserialize_const<'v, V: Visitor<'v>>(
+        &mut self,
+        expected: u64,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let offset = self.offset;
+        self.constant = Some(None);
+        let value = visitor.visit_newtype_struct(&mut *self);
+        match self.constant.take().flatten() {
+            Some(actual) if actual != expected => Err(Error::ConstMismatch {
+                expected,
+                actual,
+                offset,
+            }),
+            _ => value,
+        }
+    }
+    /// Keeps the `bits` wide integer just read as the value of the `Const` being read, if any.
+    #[inline]
+    fn record_constant(&mut self, value: u64, bits: usize) {
+        if let Some(constant) = self.constant.as_mut() {
+            *constant = Some(value & u64::MAX >> 64usize.saturating_sub(bits));
+        }
+    }
+    /// Skips `count` padding bits, checking that they are zero if asked to.
+    #[inline]
+    fn skip_padding(&mut self, count: usize) -> Result<()> {
+        let offset = self.offset;
+        let check = self.check_padding;
+        if self.read_bits(count)?.any() && check {
+            return Err(Error::InvalidPadding { offset });
+        }
+        Ok(())
+    }
+    /// Limits the total number of bytes allocated for byte buffers and strings.
+    pub fn with_limit(mut self, bytes: usize) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+    /// Limits the length of every sequence, map, byte buffer and string.
+    pub fn with_max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self
+    }
+    /// Fails with `Error::InvalidPadding` when `Align` or `Padding` bits are not all zero.
+    pub fn with_padding_check(mut self) -> Self {
+        self.check_padding = true;
+        self
+    }
 }
 
 macro_rules! impl_encoding_deserialization {
@@ -51,15 +282,17 @@ macro_rules! impl_encoding_deserialization {
         paste! {
             $(
                 fn [<deserialize_ $type>]<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value> where V: Visitor<'de> {
-                    visitor.[<visit_ $type>](E::[<deserialize_ $type>](self.read_bits(size_of::<$type>() * 8))?)
+                    let value = E::[<decode_ $type>](self)?;
+                    self.record_constant(value as u64, size_of::<$type>() * 8);
+                    visitor.[<visit_ $type>](value)
                 }
             )*
         }
     };
 }
 
-impl<'de, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding> Deserializer<'de>
-    for &'a mut BitDeserializer<'de, O, S, E>
+impl<'de, 'a, 'b, O: BitOrder, S: BitStore, E: BinaryEncoding> Deserializer<'de>
+    for &'a mut BitDeserializer<'b, O, S, E>
 where
     BitSlice<O, S>: BitField,
 {
@@ -76,46 +309,47 @@ where
     where
         V: Visitor<'de>,
     {
-        visitor.visit_bool(self.read_bits(1)[0])
+        visitor.visit_bool(self.read_bits(1)?[0])
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.read_bits(8).read_u8()?)
+        let value = E::decode_u8(self)?;
+        self.record_constant(value as u64, 8);
+        visitor.visit_u8(value)
     }
 
-    impl_encoding_deserialization![i8, i16, i32, i64, u16, u32, u64, f32, f64];
+    impl_encoding_deserialization![i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
 
     fn deserialize_char<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Unsupported)
+        visitor.visit_char(E::deserialize_char(self)?)
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Unsupported)
+        self.deserialize_string(visitor)
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Unsupported)
+        visitor.visit_string(E::deserialize_string(self)?)
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        let len = E::deserialize_len(self)?;
-        let mut bytes = vec![0u8; len];
-        self.read_bits(len * 8 as usize).read(&mut bytes[..])?;
+        let len = self.read_bytes_len()?;
+        let bytes = self.read_bytes(len)?;
         visitor.visit_bytes(&bytes[..])
     }
 
@@ -123,17 +357,19 @@ where
     where
         V: Visitor<'de>,
     {
-        let len = E::deserialize_len(self)?;
-        let mut bytes = vec![0u8; len];
-        self.read_bits(len * 8 as usize).read(&mut bytes[..])?;
-        visitor.visit_byte_buf(bytes)
+        let len = self.read_bytes_len()?;
+        visitor.visit_byte_buf(self.read_bytes(len)?)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Unsupported)
+        if E::deserialize_presence(self)? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
@@ -151,6 +387,9 @@ where
     where
         V: Visitor<'de>,
     {
+        if name == CHECKSUM_START {
+            self.checksum_start = self.base + self.offset;
+        }
         visitor.visit_unit()
     }
 
@@ -162,14 +401,26 @@ where
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        match name {
+            MSB_FIRST => self.deserialize_in_order::<Msb0, V>(visitor),
+            LSB_FIRST => self.deserialize_in_order::<Lsb0, V>(visitor),
+            Crc8::NAME => self.deserialize_checksum::<Crc8, V>(visitor),
+            Crc16::NAME => self.deserialize_checksum::<Crc16, V>(visitor),
+            Crc32::NAME => self.deserialize_checksum::<Crc32, V>(visitor),
+            Parity::NAME => self.deserialize_checksum::<Parity, V>(visitor),
+            Sum8::NAME => self.deserialize_checksum::<Sum8, V>(visitor),
+            _ => match const_value(name) {
+                Some(expected) => self.deserialize_const(expected, visitor),
+                None => visitor.visit_newtype_struct(self),
+            },
+        }
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        let len = E::deserialize_len(self)?;
+        let len = self.read_seq_len()?;
         self.deserialize_tuple(len, visitor)
     }
 
@@ -177,15 +428,15 @@ where
     where
         V: Visitor<'de>,
     {
-        struct Access<'de, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding>
+        struct Access<'b, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding>
         where
             BitSlice<O, S>: BitField,
         {
-            deserializer: &'a mut BitDeserializer<'de, O, S, E>,
+            deserializer: &'a mut BitDeserializer<'b, O, S, E>,
             len: Option<usize>,
         }
-        impl<'de, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding> SeqAccess<'de>
-            for Access<'de, 'a, O, S, E>
+        impl<'de, 'a, 'b, O: BitOrder, S: BitStore, E: BinaryEncoding> SeqAccess<'de>
+            for Access<'b, 'a, O, S, E>
         where
             BitSlice<O, S>: BitField,
         {
@@ -230,6 +481,24 @@ where
     where
         V: Visitor<'de>,
     {
+        if name == FIELD_BITS {
+            let value = E::decode_bits(self, len)?;
+            return visitor.visit_seq(SeqDeserializer::new(std::iter::once(value)));
+        } else if name == COMPACT_ENUM {
+            self.compact_variants = Some(len);
+        } else if name == KNOWN_VARIANT {
+            self.known_variant = Some(len);
+            return self.deserialize_tuple(1, visitor);
+        } else if name == ALIGN {
+            let misalignment = (self.base + self.offset).checked_rem(len).unwrap_or(0);
+            if misalignment > 0 {
+                self.skip_padding(len - misalignment)?;
+            }
+            return self.deserialize_tuple(0, visitor);
+        } else if name == PADDING {
+            self.skip_padding(len)?;
+            return self.deserialize_tuple(0, visitor);
+        }
         self.deserialize_tuple(len, visitor)
     }
 
@@ -237,7 +506,48 @@ where
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        struct Access<'b, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            deserializer: &'a mut BitDeserializer<'b, O, S, E>,
+            len: usize,
+        }
+        impl<'de, 'a, 'b, O: BitOrder, S: BitStore, E: BinaryEncoding> MapAccess<'de>
+            for Access<'b, 'a, O, S, E>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            type Error = Error;
+
+            fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+            where
+                K: DeserializeSeed<'de>,
+            {
+                if self.len > 0 {
+                    self.len -= 1;
+                    Ok(Some(seed.deserialize(&mut *self.deserializer)?))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+            where
+                V: DeserializeSeed<'de>,
+            {
+                seed.deserialize(&mut *self.deserializer)
+            }
+
+            fn size_hint(&self) -> Option<usize> {
+                Some(self.len)
+            }
+        }
+        let len = self.read_seq_len()?;
+        visitor.visit_map(Access {
+            deserializer: self,
+            len,
+        })
     }
 
     fn deserialize_struct<V>(
@@ -255,66 +565,47 @@ where
     fn deserialize_enum<V>(
         self,
         name: &'static str,
-        variants: &'static [&'static str],
+        _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<<V as Visitor<'de>>::Value>
     where
         V: Visitor<'de>,
     {
-        impl<'de, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding> serde::de::VariantAccess<'de>
-            for &'a mut BitDeserializer<'de, O, S, E>
+        struct Access<'b, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding>
         where
             BitSlice<O, S>: BitField,
         {
-            type Error = Error;
-
-            fn unit_variant(self) -> Result<()> {
-                Ok(())
-            }
-
-            fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
-            where
-                T: DeserializeSeed<'de>,
-            {
-                serde::de::DeserializeSeed::deserialize(seed, self)
-            }
-
-            fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
-            where
-                V: Visitor<'de>,
-            {
-                serde::de::Deserializer::deserialize_tuple(self, len, visitor)
-            }
-
-            fn struct_variant<V>(
-                self,
-                fields: &'static [&'static str],
-                visitor: V,
-            ) -> Result<V::Value>
-            where
-                V: Visitor<'de>,
-            {
-                serde::de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
-            }
+            deserializer: &'a mut BitDeserializer<'b, O, S, E>,
         }
-        impl<'de, 'a, O: BitOrder, S: BitStore, E: BinaryEncoding> serde::de::EnumAccess<'de>
-            for &'a mut BitDeserializer<'de, O, S, E>
+        impl<'de, 'a, 'b, O: BitOrder, S: BitStore, E: BinaryEncoding> serde::de::EnumAccess<'de>
+            for Access<'b, 'a, O, S, E>
         where
             BitSlice<O, S>: BitField,
         {
             type Error = Error;
-            type Variant = Self;
+            type Variant = &'a mut BitDeserializer<'b, O, S, E>;
 
             fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
             where
                 V: serde::de::DeserializeSeed<'de>,
             {
-                let index = E::deserialize_len(self)?;
+                let deserializer = self.deserializer;
+                let index = if let Some(index) = deserializer.known_variant.take() {
+                    index
+                } else if let Some(variants) = deserializer.compact_variants.take() {
+                    let index = E::decode_bits(deserializer, variant_bits(variants))? as usize;
+                    if index >= variants {
+                        return Err(Error::InvalidVariant { index, variants });
+                    }
+                    index
+                } else {
+                    E::deserialize_variant_index(deserializer)? as usize
+                };
                 let val: Result<_> = seed.deserialize(index.into_deserializer());
-                Ok((val?, self))
+                Ok((val?, deserializer))
             }
         }
-        visitor.visit_enum(self)
+        visitor.visit_enum(Access { deserializer: self })
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<<V as Visitor<'de>>::Value>
@@ -331,3 +622,36 @@ where
         Err(Unsupported)
     }
 }
+
+impl<'de, 'a, 'b, O: BitOrder, S: BitStore, E: BinaryEncoding> serde::de::VariantAccess<'de>
+    for &'a mut BitDeserializer<'b, O, S, E>
+where
+    BitSlice<O, S>: BitField,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        serde::de::DeserializeSeed::deserialize(seed, self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        serde::de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
@@ -0,0 +1,494 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use paste::paste;
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::de::BitDeserializer;
+use crate::encoding::{BinaryEncoding, EndianEncoding};
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+/// A variable length code for unsigned integers, laid down one bit at a time in stream order.
+pub trait IntCode {
+    /// The smallest value the code can represent.
+    const MIN: u64 = 0;
+
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()>;
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E>;
+}
+
+/// The largest value a unary run may stand for, both ways, so a single field stays under 64 Kibit.
+pub(crate) const UNARY_LIMIT: u64 = u16::MAX as u64;
+
+/// Writes the `count` least significant bits of `value`, most significant bit first.
+#[inline]
+pub(crate) fn write_msb(value: u64, count: u32, write: &mut dyn FnMut(bool)) {
+    for i in (0..count).rev() {
+        write(value >> i & 1 == 1);
+    }
+}
+
+/// Reads `count` bits, most significant bit first.
+#[inline]
+pub(crate) fn read_msb<E: serde::de::Error>(
+    count: u32,
+    read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+) -> std::result::Result<u64, E> {
+    let mut value = 0u64;
+    for _ in 0..count {
+        value = value << 1 | read()? as u64;
+    }
+    Ok(value)
+}
+
+/// Counts the zeros in front of the next one bit, consuming that one bit as well.
+#[inline]
+pub(crate) fn read_zeros<E: serde::de::Error>(
+    limit: u32,
+    read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+) -> std::result::Result<u32, E> {
+    let mut zeros = 0;
+    while !read()? {
+        zeros += 1;
+        if zeros > limit {
+            return Err(E::custom(format!(
+                "Unary prefix is longer than {} bits.",
+                limit
+            )));
+        }
+    }
+    Ok(zeros)
+}
+
+fn check_min<C: IntCode>(value: u64) -> Result<()> {
+    if value < C::MIN {
+        return Err(Error::Message(format!(
+            "Tried to encode {} with a code starting at {}.",
+            value,
+            C::MIN
+        )));
+    }
+    Ok(())
+}
+
+/// Maps a signed value onto the unsigned code numbers `0, 1, -1, 2, -2, ...` used by `se(v)`.
+#[inline]
+pub(crate) fn to_code_num(value: i64) -> Result<u64> {
+    value
+        .checked_neg()
+        .map(|negated| ((negated << 1) ^ (negated >> 63)) as u64)
+        .ok_or_else(|| Error::Message(format!("Tried to encode {} as a signed code.", value)))
+}
+
+#[inline]
+pub(crate) fn from_code_num(code_num: u64) -> Result<i64> {
+    (((code_num >> 1) as i64) ^ -((code_num & 1) as i64))
+        .checked_neg()
+        .ok_or(Error::Overflow)
+}
+
+pub(crate) fn serialize_code<C: IntCode, S: Serializer>(
+    value: u64,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut bits = Vec::new();
+    C::encode(value, &mut |bit| bits.push(bit)).map_err(serde::ser::Error::custom)?;
+    let mut tuple = serializer.serialize_tuple(bits.len())?;
+    for bit in &bits {
+        tuple.serialize_element(bit)?;
+    }
+    tuple.end()
+}
+
+pub(crate) fn deserialize_code<'de, C: IntCode, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<u64, D::Error> {
+    struct CodeVisitor<C>(PhantomData<C>);
+    impl<'de, C: IntCode> Visitor<'de> for CodeVisitor<C> {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("expecting a variable length code")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            C::decode(&mut || {
+                seq.next_element::<bool>()?
+                    .ok_or_else(|| serde::de::Error::custom("Couldn't grab next bit"))
+            })
+        }
+    }
+    deserializer.deserialize_tuple(usize::MAX, CodeVisitor::<C>(PhantomData))
+}
+
+macro_rules! impl_unsigned_code {
+    ($($code:ident),*) => {
+        $(
+            impl_unsigned_code![; $code];
+        )*
+    };
+    ($(const $param:ident: $param_ty:ty)?; $code:ident) => {
+            impl<$(const $param: $param_ty,)? T: Copy + TryInto<u64>> Serialize for $code<$($param,)? T> {
+                fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    let value = self.0.try_into().map_err(|_| {
+                        serde::ser::Error::custom("Tried to encode a value larger than 64 bits.")
+                    })?;
+                    serialize_code::<Self, S>(value, serializer)
+                }
+            }
+
+            impl<'de, $(const $param: $param_ty,)? T: TryFrom<u64>> Deserialize<'de> for $code<$($param,)? T> {
+                fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let value = deserialize_code::<Self, D>(deserializer)?;
+                    T::try_from(value).map($code).map_err(|_| {
+                        serde::de::Error::custom(format!("Decoded {} does not fit the field.", value))
+                    })
+                }
+            }
+    };
+}
+
+macro_rules! impl_code_encoding {
+    ($($type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<decode_ $type>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$type> where BitSlice<O, S>: BitField {
+                    if !INTS {
+                        return B::[<decode_ $type>](deserializer);
+                    }
+                    let value = decode_stream::<C, O, S, EN>(deserializer)? - C::MIN;
+                    if <$type>::MIN == 0 {
+                        <$type>::try_from(value).map_err(|_| Error::Overflow)
+                    } else {
+                        <$type>::try_from(from_code_num(value)?).map_err(|_| Error::Overflow)
+                    }
+                }
+                #[inline]
+                fn [<encode_ $type>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $type) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    if !INTS {
+                        return B::[<encode_ $type>](serializer, value);
+                    }
+                    let value = if <$type>::MIN == 0 {
+                        value as u64
+                    } else {
+                        to_code_num(value as i64)?
+                    };
+                    encode_stream::<C, O, S, EN>(serializer, value.checked_add(C::MIN).ok_or(Error::Overflow)?)
+                }
+            )*
+        }
+    };
+}
+
+fn decode_stream<C: IntCode, O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    deserializer: &mut BitDeserializer<O, S, E>,
+) -> Result<u64>
+where
+    BitSlice<O, S>: BitField,
+{
+    C::decode(&mut || Ok(deserializer.read_bits(1)?[0]))
+}
+
+fn encode_stream<C: IntCode, O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    serializer: &mut BitSerializer<O, S, E>,
+    value: u64,
+) -> Result<()>
+where
+    BitSlice<O, S::Alias>: BitField,
+{
+    C::encode(value, &mut |bit| serializer.vec.push(bit))
+}
+
+/// Writes lengths and enum variant indices with the code `C` and everything else through `B`.
+///
+/// Codes that cannot represent zero are given `len + C::MIN`. With `INTS` set, `u16`..`u64` are
+/// coded as well and `i16`..`i64` are mapped onto the `se(v)` code numbers first, the 128 bit
+/// integers always go through `B`.
+pub struct CodeEncoding<C, B = EndianEncoding, const INTS: bool = false>(PhantomData<(C, B)>)
+where
+    C: IntCode,
+    B: BinaryEncoding;
+
+/// Order 0 Exp-Golomb lengths, and with `INTS` set `ue(v)`/`se(v)` integers.
+pub type ExpGolombEncoding<B = EndianEncoding, const INTS: bool = false> =
+    CodeEncoding<Ue, B, INTS>;
+
+impl<C: IntCode, B: BinaryEncoding, const INTS: bool> BinaryEncoding for CodeEncoding<C, B, INTS> {
+    #[inline]
+    fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let len = decode_stream::<C, O, S, EN>(deserializer)? - C::MIN;
+        usize::try_from(len).map_err(|_| Error::Overflow)
+    }
+
+    #[inline]
+    fn serialize_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        let len = (len as u64).checked_add(C::MIN).ok_or(Error::Overflow)?;
+        encode_stream::<C, O, S, EN>(serializer, len)
+    }
+
+    delegate_primitive_encoding![B; i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    delegate_stream_encoding![B; i8, u8, i128, u128, f32, f64];
+
+    impl_code_encoding![i16, i32, i64, u16, u32, u64];
+
+    delegate_bits_encoding![B];
+
+    delegate_presence_encoding![B];
+
+    delegate_text_encoding![B];
+}
+
+/// An unsigned Exp-Golomb coded field, `ue(v)` in H.264/HEVC syntax tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ue<T = u64>(pub T);
+
+impl<T> IntCode for Ue<T> {
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        let value = value as u128 + 1;
+        let len = 128 - value.leading_zeros();
+        for _ in 1..len {
+            write(false);
+        }
+        write(true);
+        write_msb(value as u64, len - 1, write);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        let zeros = read_zeros(64, read)?;
+        let value = (1u128 << zeros | read_msb(zeros, read)? as u128) - 1;
+        u64::try_from(value).map_err(|_| E::custom("Exp-Golomb code exceeds 64 bits."))
+    }
+}
+
+/// A signed Exp-Golomb coded field, `se(v)` in H.264/HEVC syntax tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Se<T = i64>(pub T);
+
+impl<T: Copy + TryInto<i64>> Serialize for Se<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value = self.0.try_into().map_err(|_| {
+            serde::ser::Error::custom("Tried to encode a value larger than 64 bits.")
+        })?;
+        serialize_code::<Ue, S>(
+            to_code_num(value).map_err(serde::ser::Error::custom)?,
+            serializer,
+        )
+    }
+}
+
+impl<'de, T: TryFrom<i64>> Deserialize<'de> for Se<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = from_code_num(deserialize_code::<Ue, D>(deserializer)?)
+            .map_err(serde::de::Error::custom)?;
+        T::try_from(value).map(Se).map_err(|_| {
+            serde::de::Error::custom(format!("Decoded {} does not fit the field.", value))
+        })
+    }
+}
+
+/// A unary coded field, `n` one bits followed by a zero bit. Values above 65535 fail with
+/// `Error::Overflow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unary<T = u64>(pub T);
+
+impl<T> IntCode for Unary<T> {
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        if value > UNARY_LIMIT {
+            return Err(Error::Overflow);
+        }
+        for _ in 0..value {
+            write(true);
+        }
+        write(false);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        let mut value = 0u64;
+        while read()? {
+            if value == UNARY_LIMIT {
+                return Err(E::custom(format!("Unary code exceeds {}.", UNARY_LIMIT)));
+            }
+            value += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// An Elias gamma coded field, only positive values can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gamma<T = u64>(pub T);
+
+impl<T> IntCode for Gamma<T> {
+    const MIN: u64 = 1;
+
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        check_min::<Self>(value)?;
+        let len = 64 - value.leading_zeros();
+        for _ in 1..len {
+            write(false);
+        }
+        write_msb(value, len, write);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        let zeros = read_zeros(63, read)?;
+        Ok(1 << zeros | read_msb(zeros, read)?)
+    }
+}
+
+/// An Elias delta coded field, only positive values can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delta<T = u64>(pub T);
+
+impl<T> IntCode for Delta<T> {
+    const MIN: u64 = 1;
+
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        check_min::<Self>(value)?;
+        let len = 64 - value.leading_zeros();
+        Gamma::<u64>::encode(len as u64, write)?;
+        write_msb(value, len - 1, write);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        let len = Gamma::<u64>::decode(read)?;
+        if len > 64 {
+            return Err(E::custom("Elias delta code exceeds 64 bits."));
+        }
+        let len = len as u32 - 1;
+        Ok(1 << len | read_msb(len, read)?)
+    }
+}
+
+/// An Elias omega coded field, only positive values can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Omega<T = u64>(pub T);
+
+impl<T> IntCode for Omega<T> {
+    const MIN: u64 = 1;
+
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        check_min::<Self>(value)?;
+        let mut groups = Vec::new();
+        let mut value = value;
+        while value > 1 {
+            let len = 64 - value.leading_zeros();
+            groups.push((value, len));
+            value = len as u64 - 1;
+        }
+        for (group, len) in groups.into_iter().rev() {
+            write_msb(group, len, write);
+        }
+        write(false);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        let mut value = 1u64;
+        while read()? {
+            if value > 63 {
+                return Err(E::custom("Elias omega code exceeds 64 bits."));
+            }
+            value = 1 << value | read_msb(value as u32, read)?;
+        }
+        Ok(value)
+    }
+}
+
+/// A Golomb-Rice coded field, the quotient `value >> K` in unary followed by the low `K` bits.
+/// Quotients above 65535 fail with `Error::Overflow`, as for `Unary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rice<const K: u32, T = u64>(pub T);
+
+/// Rice coded lengths, and with `INTS` set Rice coded integers.
+pub type RiceEncoding<const K: u32, B = EndianEncoding, const INTS: bool = false> =
+    CodeEncoding<Rice<K>, B, INTS>;
+
+impl<const K: u32, T> IntCode for Rice<K, T> {
+    fn encode(value: u64, write: &mut dyn FnMut(bool)) -> Result<()> {
+        if K > 64 {
+            return Err(Error::Message("Rice parameter exceeds 64 bits.".to_owned()));
+        }
+        let quotient = value.checked_shr(K).unwrap_or(0);
+        if quotient > UNARY_LIMIT {
+            return Err(Error::Overflow);
+        }
+        Unary::<u64>::encode(quotient, write)?;
+        write_msb(value, K, write);
+        Ok(())
+    }
+
+    fn decode<E: serde::de::Error>(
+        read: &mut dyn FnMut() -> std::result::Result<bool, E>,
+    ) -> std::result::Result<u64, E> {
+        if K > 64 {
+            return Err(E::custom("Rice parameter exceeds 64 bits."));
+        }
+        let limit = u64::MAX.checked_shr(K).unwrap_or(0).min(UNARY_LIMIT);
+        let mut quotient = 0u64;
+        while read()? {
+            if quotient == limit {
+                return Err(E::custom("Rice code exceeds 64 bits."));
+            }
+            quotient += 1;
+        }
+        Ok(quotient.checked_shl(K).unwrap_or(0) | read_msb(K, read)?)
+    }
+}
+
+impl_unsigned_code![Ue, Unary, Gamma, Delta, Omega];
+impl_unsigned_code![const K: u32; Rice];
@@ -0,0 +1,459 @@
+use std::convert::TryFrom;
+use std::mem::size_of;
+
+use bitvec::vec::BitVec;
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, LE};
+use paste::paste;
+use serde::export::PhantomData;
+
+use crate::de::BitDeserializer;
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+pub use self::code::{
+    CodeEncoding, Delta, ExpGolombEncoding, Gamma, IntCode, Omega, Rice, RiceEncoding, Se, Ue,
+    Unary,
+};
+pub use self::text::{
+    Ascii7, CharBits, CharEncoding, LengthPrefixed, NulTerminated, Padded, StrEncoding, Utf32,
+    WithText,
+};
+pub use self::packed::PackedEncoding;
+pub use self::varint::VarintEncoding;
+pub use self::zigzag::ZigZag;
+
+macro_rules! create_primitive_encoding {
+    ($($type:ty),*) => {
+        paste! {
+            $(
+                fn [<deserialize_ $type>]<O: BitOrder, T: BitStore>(bytes: &BitSlice<O, T>) -> Result<$type> where BitSlice<O, T>: BitField;
+                fn [<serialize_ $type>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $type) -> Result<()> where BitSlice<O, T::Alias>: BitField;
+            )*
+        }
+    };
+}
+
+macro_rules! create_stream_encoding {
+    ($($type:ty),*) => {
+        paste! {
+            $(
+                /// Reads the value from the stream, by default as a fixed width value.
+                #[inline]
+                fn [<decode_ $type>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$type> where BitSlice<O, S>: BitField {
+                    Self::[<deserialize_ $type>](deserializer.read_bits(size_of::<$type>() * 8)?)
+                }
+                /// Writes the value to the stream, by default as a fixed width value.
+                #[inline]
+                fn [<encode_ $type>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $type) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    Self::[<serialize_ $type>](&mut serializer.vec, value)
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! impl_primitive_encoding {
+    ($endian:ty; $($type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<deserialize_ $type>]<O: BitOrder, T: BitStore>(mut bytes: &BitSlice<O, T>) -> Result<$type> where BitSlice<O, T>: BitField, {
+                    Ok(bytes.[<read_ $type>]::<$endian>()?)
+                }
+                #[inline]
+                fn [<serialize_ $type>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $type) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+                    vec.[<write_ $type>]::<$endian>(value)?;
+                    Ok(())
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! delegate_primitive_encoding {
+    ($inner:ty; $($type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<deserialize_ $type>]<O: BitOrder, T: BitStore>(bytes: &BitSlice<O, T>) -> Result<$type> where BitSlice<O, T>: BitField {
+                    <$inner>::[<deserialize_ $type>](bytes)
+                }
+                #[inline]
+                fn [<serialize_ $type>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $type) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+                    <$inner>::[<serialize_ $type>](vec, value)
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! delegate_stream_encoding {
+    ($inner:ty; $($type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<decode_ $type>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$type> where BitSlice<O, S>: BitField {
+                    <$inner>::[<decode_ $type>](deserializer)
+                }
+                #[inline]
+                fn [<encode_ $type>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $type) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    <$inner>::[<encode_ $type>](serializer, value)
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! delegate_len_encoding {
+    ($inner:ty; $($kind:ident: $type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<deserialize_ $kind>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$type> where BitSlice<O, S>: BitField {
+                    <$inner>::[<deserialize_ $kind>](deserializer)
+                }
+                #[inline]
+                fn [<serialize_ $kind>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $type) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    <$inner>::[<serialize_ $kind>](serializer, value)
+                }
+            )*
+        }
+    };
+}
+
+macro_rules! delegate_bits_encoding {
+    ($inner:ty) => {
+        #[inline]
+        fn decode_bits<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>, count: usize) -> Result<u64> where BitSlice<O, S>: BitField {
+            <$inner>::decode_bits(deserializer, count)
+        }
+        #[inline]
+        fn encode_bits<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: u64, count: usize) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+            <$inner>::encode_bits(serializer, value, count)
+        }
+    };
+}
+
+macro_rules! delegate_presence_encoding {
+    ($inner:ty) => {
+        #[inline]
+        fn deserialize_presence<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<bool> where BitSlice<O, S>: BitField {
+            <$inner>::deserialize_presence(deserializer)
+        }
+        #[inline]
+        fn serialize_presence<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, present: bool) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+            <$inner>::serialize_presence(serializer, present)
+        }
+    };
+}
+
+macro_rules! delegate_text_encoding {
+    ($inner:ty) => {
+        delegate_text_encoding![$inner, $inner];
+    };
+    ($str:ty, $char:ty) => {
+        #[inline]
+        fn deserialize_string<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<String> where BitSlice<O, S>: BitField {
+            <$str>::deserialize_string(deserializer)
+        }
+        #[inline]
+        fn serialize_str<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: &str) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+            <$str>::serialize_str(serializer, value)
+        }
+        #[inline]
+        fn deserialize_char<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<char> where BitSlice<O, S>: BitField {
+            <$char>::deserialize_char(deserializer)
+        }
+        #[inline]
+        fn serialize_char<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: char) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+            <$char>::serialize_char(serializer, value)
+        }
+    };
+}
+
+mod code;
+mod packed;
+mod text;
+mod varint;
+mod zigzag;
+
+pub trait BinaryEncoding {
+    fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField;
+
+    fn serialize_len<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField;
+
+    /// Deserializes a sequence or map length, by default through `deserialize_len`.
+    #[inline]
+    fn deserialize_seq_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Self::deserialize_len(deserializer)
+    }
+
+    /// Serializes a sequence or map length, by default through `serialize_len`.
+    #[inline]
+    fn serialize_seq_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        Self::serialize_len(serializer, len)
+    }
+
+    /// Deserializes a byte buffer or string length, by default through `deserialize_len`.
+    #[inline]
+    fn deserialize_bytes_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Self::deserialize_len(deserializer)
+    }
+
+    /// Serializes a byte buffer or string length, by default through `serialize_len`.
+    #[inline]
+    fn serialize_bytes_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        Self::serialize_len(serializer, len)
+    }
+
+    /// Deserializes an enum variant index, by default through `deserialize_len`.
+    #[inline]
+    fn deserialize_variant_index<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<u32>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let index = Self::deserialize_len(deserializer)?;
+        u32::try_from(index).map_err(|_| Error::Overflow)
+    }
+
+    /// Serializes an enum variant index, by default through `serialize_len`.
+    #[inline]
+    fn serialize_variant_index<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        index: u32,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        Self::serialize_len(serializer, index as usize)
+    }
+
+    create_primitive_encoding![i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    create_stream_encoding![i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    /// Reads a `u8` from the stream, by default as a whole byte.
+    #[inline]
+    fn decode_u8<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<u8>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Ok(deserializer.read_bits(8)?.read_u8()?)
+    }
+
+    /// Writes a `u8` to the stream, by default as a whole byte.
+    #[inline]
+    fn encode_u8<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: u8,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        serializer.vec.write_u8(value)?;
+        Ok(())
+    }
+
+    /// Deserializes a field of `count` bits (at most 64) such as a `UInt`, `Ranged` or `Explicit`
+    /// discriminant, by default least significant bit first.
+    #[inline]
+    fn decode_bits<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+        count: usize,
+    ) -> Result<u64>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        deserializer.load_bits(count)
+    }
+
+    /// Serializes the `count` least significant bits of `value` as a field, by default least
+    /// significant bit first.
+    #[inline]
+    fn encode_bits<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: u64,
+        count: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        serializer.store_bits(value, count);
+        Ok(())
+    }
+
+    /// Deserializes the marker in front of an `Option`, by default a single presence bit.
+    #[inline]
+    fn deserialize_presence<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<bool>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Ok(deserializer.read_bits(1)?[0])
+    }
+
+    /// Serializes the marker in front of an `Option`, by default a single presence bit.
+    #[inline]
+    fn serialize_presence<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        present: bool,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        serializer.vec.push(present);
+        Ok(())
+    }
+
+    /// Deserializes a string, by default as a length prefixed UTF-8 byte sequence.
+    #[inline]
+    fn deserialize_string<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        LengthPrefixed::deserialize_string(deserializer)
+    }
+
+    /// Serializes a string, by default as a length prefixed UTF-8 byte sequence.
+    #[inline]
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        LengthPrefixed::serialize_str(serializer, value)
+    }
+
+    /// Deserializes a char, by default as a 32 bit scalar value.
+    #[inline]
+    fn deserialize_char<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<char>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Utf32::deserialize_char(deserializer)
+    }
+
+    /// Serializes a char, by default as a 32 bit scalar value.
+    #[inline]
+    fn serialize_char<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: char,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        Utf32::serialize_char(serializer, value)
+    }
+}
+
+/// Fixed width values in the byte order `E`, lengths and enum variant indices take `LEN_BITS` bits.
+///
+/// Prefixes of 8, 16, 32 or 64 bits are written like the matching unsigned integer, any other
+/// width as that many bits, least significant bit first. Lengths that do not fit fail with
+/// `Error::Overflow`.
+pub struct EndianEncoding<E = LE, const LEN_BITS: usize = 32>(PhantomData<E>)
+where
+    E: ByteOrder;
+
+impl<E: ByteOrder, const LEN_BITS: usize> BinaryEncoding for EndianEncoding<E, LEN_BITS> {
+    #[inline]
+    fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let len = match LEN_BITS {
+            8 => deserializer.load_bits::<u8>(8)? as u64,
+            16 => Self::deserialize_u16(deserializer.read_bits(16)?)? as u64,
+            32 => Self::deserialize_u32(deserializer.read_bits(32)?)? as u64,
+            64 => Self::deserialize_u64(deserializer.read_bits(64)?)?,
+            bits if bits < 64 => deserializer.load_bits::<u64>(bits)?,
+            _ => return Err(Error::Overflow),
+        };
+        usize::try_from(len).map_err(|_| Error::Overflow)
+    }
+    #[inline]
+    fn serialize_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        let len = len as u64;
+        if LEN_BITS > 64 || (LEN_BITS < 64 && len >> LEN_BITS != 0) {
+            return Err(Error::Overflow);
+        }
+        match LEN_BITS {
+            8 => serializer.store_bits(len as u8, 8),
+            16 => Self::serialize_u16(&mut serializer.vec, len as u16)?,
+            32 => Self::serialize_u32(&mut serializer.vec, len as u32)?,
+            64 => Self::serialize_u64(&mut serializer.vec, len)?,
+            bits => serializer.store_bits(len, bits),
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn deserialize_i8<O: BitOrder, T: BitStore>(mut bytes: &BitSlice<O, T>) -> Result<i8>
+    where
+        BitSlice<O, T>: BitField,
+    {
+        Ok(bytes.read_i8()?)
+    }
+
+    #[inline]
+    fn serialize_i8<O: BitOrder, T: BitStore>(vec: &mut BitVec<O,T>, value: i8) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+        vec.write_i8(value)?;
+        Ok(())
+    }
+
+    impl_primitive_encoding![E; i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+}
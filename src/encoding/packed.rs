@@ -0,0 +1,150 @@
+use std::convert::TryFrom;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use paste::paste;
+
+use crate::de::BitDeserializer;
+use crate::encoding::BinaryEncoding;
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+/// Reads the bits as an unsigned integer, most significant bit first.
+#[inline]
+fn unpack<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> u128
+where
+    BitSlice<O, T>: BitField,
+{
+    bits.iter().fold(0, |value, bit| value << 1 | *bit as u128)
+}
+
+/// Appends the `count` least significant bits of `value`, most significant bit first.
+#[inline]
+fn pack<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: u128, count: usize)
+where
+    BitSlice<O, T::Alias>: BitField,
+{
+    vec.extend((0..count).rev().map(|bit| value >> bit & 1 == 1));
+}
+
+macro_rules! impl_packed_encoding {
+    ($($type:ty),*; $($float:ty => $repr:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<deserialize_ $type>]<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> Result<$type> where BitSlice<O, T>: BitField {
+                    Ok(unpack(bits) as $type)
+                }
+                #[inline]
+                fn [<serialize_ $type>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $type) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+                    pack(vec, value as u128, <$type>::BITS as usize);
+                    Ok(())
+                }
+            )*
+            $(
+                #[inline]
+                fn [<deserialize_ $float>]<O: BitOrder, T: BitStore>(bits: &BitSlice<O, T>) -> Result<$float> where BitSlice<O, T>: BitField {
+                    Ok(<$float>::from_bits(unpack(bits) as $repr))
+                }
+                #[inline]
+                fn [<serialize_ $float>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $float) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+                    pack(vec, value.to_bits() as u128, <$repr>::BITS as usize);
+                    Ok(())
+                }
+            )*
+        }
+    };
+}
+
+/// Lays every integer and float down most significant bit first, one bit after the other, as
+/// ASN.1 PER, Motorola CAN signals and MPEG headers do. Lengths and enum variant indices take
+/// `LEN_BITS` bits the same way, and so do the fields of `UInt`, `Int`, `Ranged`, `Explicit` and
+/// `CompactEnum`.
+///
+/// Unlike `EndianEncoding` the result does not depend on where a value starts, a `u16` at bit 3
+/// occupies bits 3 to 18 with its most significant bit first. Paired with `Msb0` the stream reads
+/// as network order. Byte buffers and strings are still copied a byte at a time.
+pub struct PackedEncoding<const LEN_BITS: usize = 32>;
+
+impl<const LEN_BITS: usize> BinaryEncoding for PackedEncoding<LEN_BITS> {
+    #[inline]
+    fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        if LEN_BITS > 64 {
+            return Err(Error::Overflow);
+        }
+        let len = unpack(deserializer.read_bits(LEN_BITS)?);
+        usize::try_from(len).map_err(|_| Error::Overflow)
+    }
+
+    #[inline]
+    fn serialize_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        let len = len as u64;
+        if LEN_BITS > 64 || (LEN_BITS < 64 && len >> LEN_BITS != 0) {
+            return Err(Error::Overflow);
+        }
+        pack(&mut serializer.vec, len as u128, LEN_BITS);
+        Ok(())
+    }
+
+    impl_packed_encoding![i8, i16, i32, i64, i128, u16, u32, u64, u128; f32 => u32, f64 => u64];
+
+    #[inline]
+    fn decode_u8<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<u8>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Ok(unpack(deserializer.read_bits(8)?) as u8)
+    }
+
+    #[inline]
+    fn encode_u8<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: u8,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        pack(&mut serializer.vec, value as u128, 8);
+        Ok(())
+    }
+
+    #[inline]
+    fn decode_bits<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+        count: usize,
+    ) -> Result<u64>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        Ok(unpack(deserializer.read_bits(count)?) as u64)
+    }
+
+    #[inline]
+    fn encode_bits<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: u64,
+        count: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        pack(&mut serializer.vec, value as u128, count);
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::io::Write;
+use std::marker::PhantomData;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use paste::paste;
+
+use crate::de::BitDeserializer;
+use crate::encoding::{BinaryEncoding, EndianEncoding};
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+/// Describes how strings are laid down in the bitstream.
+pub trait StrEncoding {
+    fn deserialize_string<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField;
+
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField;
+}
+
+/// Describes how chars are laid down in the bitstream.
+pub trait CharEncoding {
+    fn deserialize_char<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<char>
+    where
+        BitSlice<O, S>: BitField;
+
+    fn serialize_char<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: char,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField;
+}
+
+/// UTF-8 bytes prefixed with their length, written through `serialize_bytes_len`.
+pub struct LengthPrefixed;
+
+impl StrEncoding for LengthPrefixed {
+    #[inline]
+    fn deserialize_string<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let len = deserializer.read_bytes_len()?;
+        Ok(String::from_utf8(deserializer.read_bytes(len)?)?)
+    }
+
+    #[inline]
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        E::serialize_bytes_len(serializer, value.len())?;
+        serializer.vec.write_all(value.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// UTF-8 bytes followed by a single NUL byte, the string itself may not contain NUL.
+pub struct NulTerminated;
+
+impl StrEncoding for NulTerminated {
+    fn deserialize_string<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let mut bytes = Vec::new();
+        loop {
+            match deserializer.load_bits::<u8>(8)? {
+                0 => break,
+                byte => {
+                    deserializer.allocate(1)?;
+                    bytes.push(byte)
+                }
+            }
+        }
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        if value.contains('\0') {
+            return Err(Error::Message(
+                "Tried to serialize a string containing NUL as a NUL terminated string.".to_owned(),
+            ));
+        }
+        serializer.vec.write_all(value.as_bytes())?;
+        serializer.vec.write_all(&[0])?;
+        Ok(())
+    }
+}
+
+/// UTF-8 bytes padded with NUL up to exactly `N` bytes, the string itself may not contain NUL.
+pub struct Padded<const N: usize>;
+
+impl<const N: usize> StrEncoding for Padded<N> {
+    fn deserialize_string<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let mut bytes = deserializer.read_bytes(N)?;
+        if let Some(end) = bytes.iter().position(|byte| *byte == 0) {
+            bytes.truncate(end);
+        }
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        if value.contains('\0') {
+            return Err(Error::Message(
+                "Tried to serialize a string containing NUL as a NUL padded string.".to_owned(),
+            ));
+        }
+        if value.len() > N {
+            return Err(Error::Message(format!(
+                "Tried to serialize a {} byte string into a {} byte field.",
+                value.len(),
+                N
+            )));
+        }
+        serializer.vec.write_all(value.as_bytes())?;
+        serializer.vec.write_all(&vec![0u8; N - value.len()])?;
+        Ok(())
+    }
+}
+
+/// 7 bits per character prefixed with the length, only accepts ASCII strings.
+pub struct Ascii7;
+
+impl StrEncoding for Ascii7 {
+    fn deserialize_string<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<String>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let len = deserializer.read_bytes_len()?;
+        deserializer.ensure_bits(len.saturating_mul(7))?;
+        deserializer.allocate(len)?;
+        (0..len)
+            .map(|_| Ok(deserializer.load_bits::<u8>(7)? as char))
+            .collect()
+    }
+
+    fn serialize_str<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: &str,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        if !value.is_ascii() {
+            return Err(Error::Message(
+                "Tried to serialize a non ASCII string as 7 bit ASCII.".to_owned(),
+            ));
+        }
+        E::serialize_bytes_len(serializer, value.len())?;
+        for byte in value.bytes() {
+            serializer.store_bits(byte, 7);
+        }
+        Ok(())
+    }
+}
+
+/// Chars as 32 bit scalar values, written through the encoding's `u32`.
+pub struct Utf32;
+
+impl CharEncoding for Utf32 {
+    #[inline]
+    fn deserialize_char<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<char>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let value = E::deserialize_u32(deserializer.read_bits(32)?)?;
+        std::char::from_u32(value).ok_or(Error::InvalidChar(value))
+    }
+
+    #[inline]
+    fn serialize_char<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: char,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        E::serialize_u32(&mut serializer.vec, value as u32)
+    }
+}
+
+/// Chars as scalar values in exactly `BITS` bits (at most 32), `CharBits<21>` covers every scalar value.
+pub struct CharBits<const BITS: usize>;
+
+impl<const BITS: usize> CharBits<BITS> {
+    const FITS: () = assert!(BITS <= 32, "CharBits supports at most 32 bits.");
+}
+
+impl<const BITS: usize> CharEncoding for CharBits<BITS> {
+    #[inline]
+    fn deserialize_char<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, E>,
+    ) -> Result<char>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let () = Self::FITS;
+        let value = deserializer.load_bits::<u32>(BITS)?;
+        std::char::from_u32(value).ok_or(Error::InvalidChar(value))
+    }
+
+    #[inline]
+    fn serialize_char<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, E>,
+        value: char,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        let () = Self::FITS;
+        if (value as u64) >> BITS != 0 {
+            return Err(Error::Message(format!(
+                "Tried to serialize {:?} into a {} bit char.",
+                value, BITS
+            )));
+        }
+        serializer.store_bits(value as u32, BITS);
+        Ok(())
+    }
+}
+
+/// Wraps another encoding `B`, replacing how it lays down strings and chars.
+pub struct WithText<B = EndianEncoding, Str = LengthPrefixed, Ch = Utf32>(
+    PhantomData<(B, Str, Ch)>,
+)
+where
+    B: BinaryEncoding,
+    Str: StrEncoding,
+    Ch: CharEncoding;
+
+impl<B: BinaryEncoding, Str: StrEncoding, Ch: CharEncoding> BinaryEncoding
+    for WithText<B, Str, Ch>
+{
+    delegate_len_encoding![B; len: usize, seq_len: usize, bytes_len: usize, variant_index: u32];
+
+    delegate_primitive_encoding![B; i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    delegate_stream_encoding![B; i8, u8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    delegate_bits_encoding![B];
+
+    delegate_presence_encoding![B];
+
+    delegate_text_encoding![Str, Ch];
+}
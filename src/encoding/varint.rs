@@ -0,0 +1,167 @@
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use paste::paste;
+
+use crate::de::BitDeserializer;
+use crate::encoding::{BinaryEncoding, EndianEncoding};
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+/// Writes `value` as unsigned LEB128, seven bits per byte with the high bit marking continuation.
+pub(crate) fn encode_leb128<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    serializer: &mut BitSerializer<O, S, E>,
+    mut value: u64,
+) where
+    BitSlice<O, S::Alias>: BitField,
+{
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            serializer.store_bits(byte, 8);
+            return;
+        }
+        serializer.store_bits(byte | 0x80, 8);
+    }
+}
+
+pub(crate) fn decode_leb128<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    deserializer: &mut BitDeserializer<O, S, E>,
+) -> Result<u64>
+where
+    BitSlice<O, S>: BitField,
+{
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = deserializer.load_bits::<u8>(8)?;
+        if shift > 63 || (shift == 63 && byte & 0x7E != 0) {
+            return Err(Error::Overflow);
+        }
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Writes `value` as signed LEB128, the last byte's sixth bit carries the sign.
+pub(crate) fn encode_sleb128<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    serializer: &mut BitSerializer<O, S, E>,
+    mut value: i64,
+) where
+    BitSlice<O, S::Alias>: BitField,
+{
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            serializer.store_bits(byte, 8);
+            return;
+        }
+        serializer.store_bits(byte | 0x80, 8);
+    }
+}
+
+pub(crate) fn decode_sleb128<O: BitOrder, S: BitStore, E: BinaryEncoding>(
+    deserializer: &mut BitDeserializer<O, S, E>,
+) -> Result<i64>
+where
+    BitSlice<O, S>: BitField,
+{
+    let mut value = 0i64;
+    let mut shift = 0;
+    loop {
+        let byte = deserializer.load_bits::<u8>(8)?;
+        if shift > 63 || (shift == 63 && byte & 0x7F != 0 && byte & 0x7F != 0x7F) {
+            return Err(Error::Overflow);
+        }
+        value |= ((byte & 0x7F) as i64) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift < 64 && byte & 0x40 != 0 {
+                value |= -1i64 << shift;
+            }
+            return Ok(value);
+        }
+    }
+}
+
+macro_rules! impl_varint_encoding {
+    ($wide:ty, $encode:ident, $decode:ident; $($type:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<decode_ $type>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$type> where BitSlice<O, S>: BitField {
+                    if INTS {
+                        <$type>::try_from($decode(deserializer)?).map_err(|_| Error::Overflow)
+                    } else {
+                        B::[<decode_ $type>](deserializer)
+                    }
+                }
+                #[inline]
+                fn [<encode_ $type>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $type) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    if INTS {
+                        $encode(serializer, value as $wide);
+                        Ok(())
+                    } else {
+                        B::[<encode_ $type>](serializer, value)
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Writes lengths and enum variant indices as LEB128 and everything else through `B`.
+///
+/// With `INTS` set, `u16`..`u64` are written as LEB128 and `i16`..`i64` as signed LEB128 as well,
+/// `i8`, the 128 bit integers and the floats stay fixed width.
+pub struct VarintEncoding<B = EndianEncoding, const INTS: bool = false>(PhantomData<B>)
+where
+    B: BinaryEncoding;
+
+impl<B: BinaryEncoding, const INTS: bool> BinaryEncoding for VarintEncoding<B, INTS> {
+    #[inline]
+    fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<usize>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        usize::try_from(decode_leb128(deserializer)?).map_err(|_| Error::Overflow)
+    }
+
+    #[inline]
+    fn serialize_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        len: usize,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        encode_leb128(serializer, len as u64);
+        Ok(())
+    }
+
+    delegate_primitive_encoding![B; i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64];
+
+    delegate_stream_encoding![B; i8, u8, i128, u128, f32, f64];
+
+    impl_varint_encoding![u64, encode_leb128, decode_leb128; u16, u32, u64];
+
+    impl_varint_encoding![i64, encode_sleb128, decode_sleb128; i16, i32, i64];
+
+    delegate_bits_encoding![B];
+
+    delegate_presence_encoding![B];
+
+    delegate_text_encoding![B];
+}
@@ -0,0 +1,103 @@
+use std::marker::PhantomData;
+
+use bitvec::field::BitField;
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use paste::paste;
+
+use crate::de::BitDeserializer;
+use crate::encoding::{BinaryEncoding, EndianEncoding};
+use crate::ser::BitSerializer;
+use crate::Result;
+
+macro_rules! impl_zigzag_encoding {
+    ($($signed:ty => $unsigned:ty),*) => {
+        paste! {
+            $(
+                #[inline]
+                fn [<deserialize_ $signed>]<O: BitOrder, T: BitStore>(bytes: &BitSlice<O, T>) -> Result<$signed> where BitSlice<O, T>: BitField {
+                    let value = B::[<deserialize_ $unsigned>](bytes)?;
+                    Ok((value >> 1) as $signed ^ -((value & 1) as $signed))
+                }
+                #[inline]
+                fn [<serialize_ $signed>]<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: $signed) -> Result<()> where BitSlice<O, T::Alias>: BitField {
+                    B::[<serialize_ $unsigned>](vec, (value << 1 ^ value >> (<$signed>::BITS - 1)) as $unsigned)
+                }
+                #[inline]
+                fn [<decode_ $signed>]<O: BitOrder, S: BitStore, EN: BinaryEncoding>(deserializer: &mut BitDeserializer<O, S, EN>) -> Result<$signed> where BitSlice<O, S>: BitField {
+                    let value = B::[<decode_ $unsigned>](deserializer)?;
+                    Ok((value >> 1) as $signed ^ -((value & 1) as $signed))
+                }
+                #[inline]
+                fn [<encode_ $signed>]<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(serializer: &mut BitSerializer<O, S, EN>, value: $signed) -> Result<()> where BitSlice<O, S::Alias>: BitField {
+                    B::[<encode_ $unsigned>](serializer, (value << 1 ^ value >> (<$signed>::BITS - 1)) as $unsigned)
+                }
+            )*
+        }
+    };
+}
+
+/// Maps `i16`..`i128` through ZigZag onto the matching unsigned integer of `B`, so small negative
+/// values stay small once `B` writes unsigned integers with a variable width.
+///
+/// `i8` is mapped as well and written as `B`'s `i8`, everything else goes through `B` unchanged.
+pub struct ZigZag<B = EndianEncoding>(PhantomData<B>)
+where
+    B: BinaryEncoding;
+
+impl<B: BinaryEncoding> BinaryEncoding for ZigZag<B> {
+    delegate_len_encoding![B; len: usize, seq_len: usize, bytes_len: usize, variant_index: u32];
+
+    delegate_primitive_encoding![B; u16, u32, u64, u128, f32, f64];
+
+    delegate_stream_encoding![B; u8, u16, u32, u64, u128, f32, f64];
+
+    impl_zigzag_encoding![i16 => u16, i32 => u32, i64 => u64, i128 => u128];
+
+    #[inline]
+    fn deserialize_i8<O: BitOrder, T: BitStore>(bytes: &BitSlice<O, T>) -> Result<i8>
+    where
+        BitSlice<O, T>: BitField,
+    {
+        let value = B::deserialize_i8(bytes)? as u8;
+        Ok((value >> 1) as i8 ^ -((value & 1) as i8))
+    }
+
+    #[inline]
+    fn serialize_i8<O: BitOrder, T: BitStore>(vec: &mut BitVec<O, T>, value: i8) -> Result<()>
+    where
+        BitSlice<O, T::Alias>: BitField,
+    {
+        B::serialize_i8(vec, value << 1 ^ value >> 7)
+    }
+
+    #[inline]
+    fn decode_i8<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+        deserializer: &mut BitDeserializer<O, S, EN>,
+    ) -> Result<i8>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let value = B::decode_i8(deserializer)? as u8;
+        Ok((value >> 1) as i8 ^ -((value & 1) as i8))
+    }
+
+    #[inline]
+    fn encode_i8<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+        serializer: &mut BitSerializer<O, S, EN>,
+        value: i8,
+    ) -> Result<()>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        B::encode_i8(serializer, value << 1 ^ value >> 7)
+    }
+
+    delegate_bits_encoding![B];
+
+    delegate_presence_encoding![B];
+
+    delegate_text_encoding![B];
+}
@@ -0,0 +1,84 @@
+use std::fmt::Formatter;
+use std::mem::size_of;
+
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Writes the wrapped number most significant byte first, whatever the encoding's byte order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BigEndian<T>(pub T);
+
+/// Writes the wrapped number least significant byte first, whatever the encoding's byte order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LittleEndian<T>(pub T);
+
+/// Serializes bytes as a tuple of `u8`, which the bit serializer writes as they are.
+fn serialize_bytes<S: Serializer, const N: usize>(
+    bytes: [u8; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for byte in &bytes {
+        tuple.serialize_element(byte)?;
+    }
+    tuple.end()
+}
+
+struct BytesVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for BytesVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "expecting {} bytes", N)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+        }
+        Ok(bytes)
+    }
+}
+
+macro_rules! impl_endian_wrapper {
+    ($wrapper:ident, $to_bytes:ident, $from_bytes:ident; $($type:ty),*) => {
+        $(
+            impl Serialize for $wrapper<$type> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serialize_bytes(self.0.$to_bytes(), serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $wrapper<$type> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    const N: usize = size_of::<$type>();
+                    let bytes = deserializer.deserialize_tuple(N, BytesVisitor::<N>)?;
+                    Ok($wrapper(<$type>::$from_bytes(bytes)))
+                }
+            }
+        )*
+    };
+}
+
+impl_endian_wrapper![
+    BigEndian, to_be_bytes, from_be_bytes;
+    i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64
+];
+impl_endian_wrapper![
+    LittleEndian, to_le_bytes, from_le_bytes;
+    i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64
+];
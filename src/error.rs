@@ -8,6 +8,77 @@ pub enum Error {
     Message(String),
     Io(std::io::Error),
     Unsupported,
+    Utf8(std::string::FromUtf8Error),
+    InvalidChar(u32),
+    UnexpectedEof {
+        needed_bits: usize,
+        available_bits: usize,
+        offset: usize,
+    },
+    LimitExceeded {
+        limit: usize,
+        requested: usize,
+    },
+    Overflow,
+    InvalidVariant {
+        index: usize,
+        variants: usize,
+    },
+    TrailingBits {
+        remaining: usize,
+    },
+    InvalidPadding {
+        offset: usize,
+    },
+    ConstMismatch {
+        expected: u64,
+        actual: u64,
+        offset: usize,
+    },
+    ChecksumMismatch {
+        expected: u64,
+        actual: u64,
+        offset: usize,
+    },
+}
+
+impl Error {
+    /// Moves the bit offset an error reports by `bits`, for errors raised in a nested stream.
+    pub(crate) fn offset_by(self, bits: usize) -> Self {
+        match self {
+            Error::UnexpectedEof {
+                needed_bits,
+                available_bits,
+                offset,
+            } => Error::UnexpectedEof {
+                needed_bits,
+                available_bits,
+                offset: offset + bits,
+            },
+            Error::InvalidPadding { offset } => Error::InvalidPadding {
+                offset: offset + bits,
+            },
+            Error::ConstMismatch {
+                expected,
+                actual,
+                offset,
+            } => Error::ConstMismatch {
+                expected,
+                actual,
+                offset: offset + bits,
+            },
+            Error::ChecksumMismatch {
+                expected,
+                actual,
+                offset,
+            } => Error::ChecksumMismatch {
+                expected,
+                actual,
+                offset: offset + bits,
+            },
+            other => other,
+        }
+    }
 }
 
 impl Display for Error {
@@ -41,3 +112,9 @@ impl From<std::io::Error> for Error {
         Error::Io(e)
     }
 }
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        Error::Utf8(e)
+    }
+}
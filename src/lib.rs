@@ -7,16 +7,38 @@ use bitvec::store::BitStore;
 use bitvec::vec::BitVec;
 use serde::{Deserialize, Serialize};
 
-pub use container::{BitContainer, ContainerSize};
+pub use checksum::{Checksum, ChecksumStart, Crc, Crc16, Crc32, Crc8, Parity, Sum8};
+pub use constant::Const;
+pub use container::{BitContainer, ContainerSize, Int, Ranged, UInt};
+pub use de::BitDeserializer;
+pub use endian::{BigEndian, LittleEndian};
 pub use error::{Error, Result};
+pub use options::{options, Options, TrailingBits};
+pub use padding::{Align, Padding};
+pub use order::{Lsb, Msb};
+pub use ser::BitSerializer;
+pub use variant::{CompactEnum, Discriminants, Explicit, Variants};
 
-pub use crate::encoding::{BinaryEncoding, EndianEncoding};
+pub use crate::encoding::{
+    Ascii7, BinaryEncoding, CharBits, CharEncoding, CodeEncoding, Delta, EndianEncoding,
+    ExpGolombEncoding, Gamma, IntCode, LengthPrefixed, NulTerminated, Omega, PackedEncoding,
+    Padded, Rice, RiceEncoding, Se, StrEncoding, Ue, Unary, Utf32, VarintEncoding, WithText,
+    ZigZag,
+};
 
+mod checksum;
+mod constant;
 mod container;
 mod de;
+#[macro_use]
 mod encoding;
+mod endian;
 mod error;
+mod options;
+mod order;
+mod padding;
 mod ser;
+mod variant;
 
 pub fn deserialize<'a, T: Deserialize<'a>, O: BitOrder, S: BitStore, E: BinaryEncoding>(
     bits: &'a BitSlice<O, S>,
@@ -34,10 +56,8 @@ pub fn serialize<T: Serialize, O: BitOrder + 'static, S: BitStore, E: BinaryEnco
 where
     BitSlice<O, S::Alias>: BitField,
 {
-    let mut serializer = ser::BitSerializer::<O, S, E> {
-        vec: BitVec::new(), //todo we can reduce allocation costs by pre-allocating with struct bit size ~35 ns
-        endian: Default::default(),
-    };
+    //todo we can reduce allocation costs by pre-allocating with struct bit size ~35 ns
+    let mut serializer = ser::BitSerializer::<O, S, E>::new();
     value.serialize(&mut serializer)?;
     Ok(serializer.vec.clone())
 }
@@ -46,14 +66,31 @@ where
 mod tests {
     extern crate test;
 
+    use std::collections::{BTreeMap, HashMap};
+    use std::convert::TryFrom;
+    use std::fmt::Debug;
     use test::Bencher;
 
-    use bitvec::order::Lsb0;
+    use bitvec::order::{Lsb0, Msb0};
     use bitvec::view::BitView;
+    use bitvec::field::BitField;
+    use bitvec::order::BitOrder;
+    use bitvec::slice::BitSlice;
+    use bitvec::store::BitStore;
+    use bitvec::vec::BitVec;
+    use byteorder::{BE, LE};
+    use paste::paste;
     use serde::{Deserialize, Serialize};
 
     use crate::encoding::EndianEncoding;
-    use crate::{deserialize, serialize, BitContainer, ContainerSize};
+    use crate::{
+        deserialize, options, serialize, Align, Ascii7, BigEndian, BinaryEncoding, BitContainer,
+        BitDeserializer, BitSerializer, CharBits, Checksum, ChecksumStart, CodeEncoding,
+        CompactEnum, Const, ContainerSize, Crc, Crc16, Crc32, Crc8, Delta, Discriminants, Error,
+        ExpGolombEncoding, Explicit, Gamma, Int, LittleEndian, Lsb, Msb, NulTerminated, Omega,
+        PackedEncoding, Padded, Padding, Parity, Ranged, Result, Rice, RiceEncoding, Se, Sum8,
+        TrailingBits, UInt, Ue, Unary, Variants, VarintEncoding, WithText, ZigZag,
+    };
 
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct BitTest(bool, bool, bool, bool, bool, bool, bool, bool);
@@ -131,6 +168,62 @@ mod tests {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct TestEnumStruct(Vec<TestEnum>);
 
+    #[test]
+    fn varint_vector_test() {
+        let obj = VectorTest(1.104321, vec![true, true, false, false, true, true]);
+        let bits = serialize::<_, Lsb0, u8, VarintEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 8 + 6);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, VarintEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let obj = VectorTest(0.0, vec![false; 300]);
+        let bits = serialize::<_, Lsb0, u8, VarintEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 16 + 300);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, VarintEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[test]
+    fn varint_enum_test() {
+        type Varint = VarintEncoding<EndianEncoding, true>;
+        let test = TestEnumStruct(vec![
+            TestEnum::False(true),
+            TestEnum::True(102040),
+            TestEnum::True(u32::MAX),
+            TestEnum::False(false),
+        ]);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&test).unwrap();
+        assert_eq!(bits.len(), 8 + (8 + 1) + (8 + 24) + (8 + 40) + (8 + 1));
+        let (test2, _) = deserialize::<TestEnumStruct, _, _, Varint>(bits.as_bitslice()).unwrap();
+        assert_eq!(test, test2);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct IntTest(i16, i32, i64, u64);
+
+    #[test]
+    fn varint_int_test() {
+        type Varint = VarintEncoding<EndianEncoding, true>;
+        let obj = IntTest(-1, 63, -64, 127);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&obj).unwrap();
+        assert_eq!(bits.len(), 4 * 8);
+        assert_eq!(deserialize::<IntTest, _, _, Varint>(bits.as_bitslice()).unwrap().0, obj);
+
+        let obj = IntTest(i16::MIN, i32::MAX, i64::MIN, u64::MAX);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&obj).unwrap();
+        assert_eq!(bits.len(), (3 + 5 + 10 + 10) * 8);
+        assert_eq!(deserialize::<IntTest, _, _, Varint>(bits.as_bitslice()).unwrap().0, obj);
+
+        // 70000 does not fit the i16 field once decoded
+        let bits = serialize::<_, Lsb0, u8, Varint>(&(70000i32, 0i32, 0i64, 0u64)).unwrap();
+        match deserialize::<IntTest, _, _, Varint>(bits.as_bitslice()) {
+            Err(Error::Overflow) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
     #[test]
     fn enum_test() {
         let test = TestEnumStruct(vec![
@@ -144,6 +237,1073 @@ mod tests {
         assert_eq!(test, test2);
     }
 
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct StringTest(String, char, bool);
+
+    #[test]
+    fn string_test() {
+        let obj = StringTest("bits".to_owned(), 'é', true);
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 32 + 4 * 8 + 32 + 1);
+        let (obj2, _) = deserialize::<StringTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[test]
+    fn text_encoding_test() {
+        type Nul = WithText<EndianEncoding, NulTerminated, CharBits<21>>;
+        let obj = StringTest("bits".to_owned(), '\u{10FFFF}', true);
+        let bits = serialize::<_, Lsb0, u8, Nul>(&obj).unwrap();
+        assert_eq!(bits.len(), 5 * 8 + 21 + 1);
+        assert_eq!(deserialize::<StringTest, _, _, Nul>(bits.as_bitslice()).unwrap().0, obj);
+
+        type Fixed = WithText<EndianEncoding, Padded<6>>;
+        let bits = serialize::<_, Lsb0, u8, Fixed>(&obj).unwrap();
+        assert_eq!(bits.len(), 6 * 8 + 32 + 1);
+        assert_eq!(deserialize::<StringTest, _, _, Fixed>(bits.as_bitslice()).unwrap().0, obj);
+        assert!(serialize::<_, Lsb0, u8, WithText<EndianEncoding, Padded<2>>>(&obj).is_err());
+        let nul = StringTest("a\0b".to_owned(), 'a', true);
+        assert!(serialize::<_, Lsb0, u8, Fixed>(&nul).is_err());
+
+        type Ascii = WithText<EndianEncoding, Ascii7>;
+        let bits = serialize::<_, Lsb0, u8, Ascii>(&obj).unwrap();
+        assert_eq!(bits.len(), 32 + 4 * 7 + 32 + 1);
+        assert_eq!(deserialize::<StringTest, _, _, Ascii>(bits.as_bitslice()).unwrap().0, obj);
+        let obj = StringTest("bïts".to_owned(), 'a', true);
+        assert!(serialize::<_, Lsb0, u8, Ascii>(&obj).is_err());
+        let data = [0xFFu8, 0xFF, 0xFF, 0x0F, b'a'];
+        let mut limited = BitDeserializer::<Lsb0, u8, Ascii>::new(data.view_bits()).with_limit(16);
+        match String::deserialize(&mut limited) {
+            Err(Error::UnexpectedEof { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct OptionTest(Option<u16>, Option<u16>, Option<bool>);
+
+    #[test]
+    fn option_test() {
+        let obj = OptionTest(Some(0x1234), None, Some(false));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 1 + 16 + 1 + 1 + 1);
+        let (obj2, _) = deserialize::<OptionTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct MapTest(HashMap<u16, bool>, BTreeMap<u8, Vec<bool>>);
+
+    #[test]
+    fn map_test() {
+        let obj = MapTest(
+            (0..32).map(|i| (i * 7, i % 3 == 0)).collect(),
+            vec![(1, vec![true]), (2, vec![false, true])].into_iter().collect(),
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 32 + 32 * 17 + 32 + 2 * (8 + 32) + 3);
+        let (obj2, _) = deserialize::<MapTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[test]
+    fn canonical_map_test() {
+        let canonical = |map: &HashMap<u16, bool>| {
+            let mut serializer =
+                BitSerializer::<Lsb0, u8, EndianEncoding>::new().with_canonical_maps();
+            map.serialize(&mut serializer).unwrap();
+            serializer.vec
+        };
+        let a: HashMap<u16, bool> = (0..64).map(|i| (i, i % 2 == 0)).collect();
+        let b: HashMap<u16, bool> = (0..64).rev().map(|i| (i, i % 2 == 0)).collect();
+        assert_eq!(canonical(&a), canonical(&b));
+        let bits = canonical(&a);
+        let (a2, _) =
+            deserialize::<HashMap<u16, bool>, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(a, a2);
+
+        let sorted: BTreeMap<u16, bool> = a.iter().map(|(&k, &v)| (k, v)).collect();
+        let options = options().with_canonical_maps();
+        assert_eq!(options.serialize::<_, Lsb0, u8>(&a).unwrap(), bits);
+        assert_eq!(options.serialize::<_, Lsb0, u8>(&sorted).unwrap(), bits);
+    }
+
+    fn assert_truncation_fails<T>(value: T)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Debug,
+    {
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&value).unwrap();
+        for len in 0..bits.len() {
+            match deserialize::<T, _, _, EndianEncoding>(&bits[..len]) {
+                Err(Error::UnexpectedEof {
+                    needed_bits,
+                    available_bits,
+                    offset,
+                }) => {
+                    assert!(needed_bits > available_bits);
+                    assert_eq!(offset + available_bits, len);
+                }
+                other => panic!("{:?} truncated to {} bits gave {:?}", value, len, other),
+            }
+        }
+    }
+
+    #[test]
+    fn truncation_test() {
+        assert_truncation_fails(true);
+        assert_truncation_fails(0xA5u8);
+        assert_truncation_fails(-3i8);
+        assert_truncation_fails(-300i16);
+        assert_truncation_fails(0xBEEFu16);
+        assert_truncation_fails(-70000i32);
+        assert_truncation_fails(0xDEADBEEFu32);
+        assert_truncation_fails(-1i64);
+        assert_truncation_fails(u64::MAX);
+        assert_truncation_fails(-5i128);
+        assert_truncation_fails(u128::MAX);
+        assert_truncation_fails(1.5f32);
+        assert_truncation_fails(-2.25f64);
+        assert_truncation_fails('x');
+        assert_truncation_fails("bits".to_owned());
+        assert_truncation_fails(Some(7u16));
+        assert_truncation_fails(vec![1u32, 2]);
+        assert_truncation_fails(TestEnum::True(5));
+        assert_truncation_fails(MapTest(
+            vec![(1, true)].into_iter().collect(),
+            BTreeMap::new(),
+        ));
+    }
+
+    #[test]
+    fn limit_test() {
+        // a 4 byte header claiming a 4 GiB string
+        let data = [0xFFu8, 0xFF, 0xFF, 0xFF, 0x00];
+        let mut deserializer =
+            BitDeserializer::<Lsb0, u8, EndianEncoding>::new(data.view_bits::<Lsb0>());
+        match String::deserialize(&mut deserializer) {
+            Err(Error::UnexpectedEof { needed_bits, .. }) => {
+                assert_eq!(needed_bits, 0xFFFFFFFF * 8)
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&"limits".to_owned()).unwrap();
+        let mut deserializer =
+            BitDeserializer::<Lsb0, u8, EndianEncoding>::new(bits.as_bitslice()).with_limit(5);
+        match String::deserialize(&mut deserializer) {
+            Err(Error::LimitExceeded { limit, requested }) => {
+                assert_eq!((limit, requested), (5, 6))
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&vec![true; 10]).unwrap();
+        let mut deserializer =
+            BitDeserializer::<Lsb0, u8, EndianEncoding>::new(bits.as_bitslice()).with_max_len(9);
+        match Vec::<bool>::deserialize(&mut deserializer) {
+            Err(Error::LimitExceeded { limit, requested }) => {
+                assert_eq!((limit, requested), (9, 10))
+            }
+            other => panic!("{:?}", other),
+        }
+        let mut deserializer =
+            BitDeserializer::<Lsb0, u8, EndianEncoding>::new(bits.as_bitslice()).with_max_len(10);
+        assert_eq!(Vec::<bool>::deserialize(&mut deserializer).unwrap(), vec![true; 10]);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct SpsTest {
+        profile_idc: u8,
+        seq_parameter_set_id: Ue<u32>,
+        log2_max_frame_num_minus4: Ue<u8>,
+        offset_for_non_ref_pic: Se<i32>,
+        frame_mbs_only_flag: bool,
+    }
+
+    #[test]
+    fn exp_golomb_test() {
+        let sps = SpsTest {
+            profile_idc: 100,
+            seq_parameter_set_id: Ue(0),
+            log2_max_frame_num_minus4: Ue(3),
+            offset_for_non_ref_pic: Se(-2),
+            frame_mbs_only_flag: true,
+        };
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&sps).unwrap();
+        assert_eq!(bits.len(), 8 + 1 + 5 + 5 + 1);
+        assert_eq!(
+            bits[8..].iter().copied().collect::<Vec<bool>>(),
+            vec![true, false, false, true, false, false, false, false, true, false, true, true]
+        );
+        let (sps2, _) = deserialize::<SpsTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(sps, sps2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&Ue(u64::MAX)).unwrap();
+        assert_eq!(bits.len(), 129);
+        assert_eq!(
+            deserialize::<Ue, _, _, EndianEncoding>(bits.as_bitslice()).unwrap().0,
+            Ue(u64::MAX)
+        );
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Se(i64::MIN)).is_err());
+        assert!(deserialize::<Ue<u8>, _, _, EndianEncoding>(bits.as_bitslice()).is_err());
+    }
+
+    #[test]
+    fn exp_golomb_encoding_test() {
+        let obj = VectorTest(1.104321, vec![true, true, false, false, true, true]);
+        let bits = serialize::<_, Lsb0, u8, ExpGolombEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 5 + 6);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, ExpGolombEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        type Golomb = ExpGolombEncoding<EndianEncoding, true>;
+        let obj = IntTest(-1, 63, -64, 127);
+        let bits = serialize::<_, Lsb0, u8, Golomb>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 13 + 15 + 15);
+        assert_eq!(deserialize::<IntTest, _, _, Golomb>(bits.as_bitslice()).unwrap().0, obj);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct UniversalTest(Unary<u8>, Gamma<u32>, Delta<u32>, Omega<u64>);
+
+    #[test]
+    fn universal_code_test() {
+        let obj = UniversalTest(Unary(3), Gamma(5), Delta(10), Omega(16));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 4 + 5 + 8 + 11);
+        let (obj2, _) =
+            deserialize::<UniversalTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let obj = UniversalTest(Unary(0), Gamma(1), Delta(1), Omega(1));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 4);
+        let (obj2, _) =
+            deserialize::<UniversalTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let obj = UniversalTest(Unary(1), Gamma(u32::MAX), Delta(u32::MAX), Omega(u64::MAX));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 2 + 63 + (11 + 31) + (2 + 3 + 6 + 64 + 1));
+        let (obj2, _) =
+            deserialize::<UniversalTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Gamma(0)).is_err());
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Omega(0)).is_err());
+    }
+
+    #[test]
+    fn universal_encoding_test() {
+        let obj = VectorTest(1.104321, vec![true, true, false, false, true, true]);
+        let bits = serialize::<_, Lsb0, u8, CodeEncoding<Gamma>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 5 + 6);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, CodeEncoding<Gamma>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, CodeEncoding<Unary>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 7 + 6);
+        let bits = serialize::<_, Lsb0, u8, CodeEncoding<Delta>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 5 + 6);
+        let bits = serialize::<_, Lsb0, u8, CodeEncoding<Omega>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 6 + 6);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, CodeEncoding<Omega>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[test]
+    fn unary_limit_test() {
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Unary(65535u32)).is_ok());
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Unary(u64::MAX)).is_err());
+        let lengths = serialize::<_, Lsb0, u8, CodeEncoding<Unary>>(&vec![(); 1 << 16]);
+        assert!(matches!(lengths, Err(Error::Overflow)));
+        let data = [0xFFu8; 8193];
+        assert!(deserialize::<Unary, _, _, EndianEncoding>(data.view_bits::<Lsb0>()).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct RiceTest(Rice<3, u16>, Rice<0, u8>, Rice<64>);
+
+    #[test]
+    fn rice_test() {
+        let obj = RiceTest(Rice(21), Rice(2), Rice(u64::MAX));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), (3 + 3) + 3 + (1 + 64));
+        let (obj2, _) = deserialize::<RiceTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        // a run of ones longer than any quotient that fits, rejected before the input runs out
+        let data = [0xFFu8; 4];
+        match deserialize::<Rice<60>, _, _, EndianEncoding>(data.view_bits::<Lsb0>()) {
+            Err(Error::Message(_)) => {}
+            other => panic!("{:?}", other),
+        }
+        match deserialize::<Rice<0>, _, _, EndianEncoding>(data.view_bits::<Lsb0>()) {
+            Err(Error::UnexpectedEof { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Rice::<0>(u64::MAX)).is_err());
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Rice::<16>(u64::MAX >> 32)).is_ok());
+        let data = [0xFFu8; 8193];
+        assert!(deserialize::<Rice<0>, _, _, EndianEncoding>(data.view_bits::<Lsb0>()).is_err());
+
+        type Golomb = RiceEncoding<4, EndianEncoding, true>;
+        let obj = IntTest(-1, 63, -64, 127);
+        let bits = serialize::<_, Lsb0, u8, Golomb>(&obj).unwrap();
+        assert_eq!(bits.len(), 5 + 12 + 13 + 12);
+        assert_eq!(deserialize::<IntTest, _, _, Golomb>(bits.as_bitslice()).unwrap().0, obj);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct WideTest(u128, i128);
+
+    #[test]
+    fn wide_int_test() {
+        let obj = WideTest(0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10, -2);
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 2 * 128);
+        assert_eq!(bits.as_slice()[0], 0x10);
+        assert_eq!(bits.as_slice()[16], 0xFE);
+        let (obj2, _) = deserialize::<WideTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE>>(&obj).unwrap();
+        assert_eq!(bits.as_slice()[0], 0x01);
+        assert_eq!(bits.as_slice()[31], 0xFE);
+        let (obj2, _) =
+            deserialize::<WideTest, _, _, EndianEncoding<BE>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        // the 128 bit integers stay fixed width, only the sign mapping applies
+        type Varint = ZigZag<VarintEncoding<EndianEncoding, true>>;
+        let obj = WideTest(u128::MAX, i128::MIN);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&obj).unwrap();
+        assert_eq!(bits.len(), 2 * 128);
+        let (obj2, _) = deserialize::<WideTest, _, _, Varint>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[test]
+    fn zigzag_test() {
+        assert_eq!(
+            serialize::<_, Lsb0, u8, ZigZag>(&(-1i16, 1i32, i64::MIN, -1i8)).unwrap(),
+            serialize::<_, Lsb0, u8, EndianEncoding>(&(1u16, 2u32, u64::MAX, 1u8)).unwrap()
+        );
+
+        type Varint = ZigZag<VarintEncoding<EndianEncoding, true>>;
+        let obj = IntTest(-1, 63, -64, 127);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&obj).unwrap();
+        assert_eq!(bits.len(), 4 * 8);
+        assert_eq!(deserialize::<IntTest, _, _, Varint>(bits.as_bitslice()).unwrap().0, obj);
+
+        let obj = IntTest(i16::MIN, i32::MAX, i64::MIN, u64::MAX);
+        let bits = serialize::<_, Lsb0, u8, Varint>(&obj).unwrap();
+        assert_eq!(bits.len(), (3 + 5 + 10 + 10) * 8);
+        assert_eq!(deserialize::<IntTest, _, _, Varint>(bits.as_bitslice()).unwrap().0, obj);
+
+        type Golomb = ZigZag<ExpGolombEncoding<EndianEncoding, true>>;
+        let obj = IntTest(-1, 63, -64, 127);
+        let bits = serialize::<_, Lsb0, u8, Golomb>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 13 + 15 + 15);
+        assert_eq!(deserialize::<IntTest, _, _, Golomb>(bits.as_bitslice()).unwrap().0, obj);
+
+        let obj = (i8::MIN, i8::MAX, 0i8);
+        let bits = serialize::<_, Lsb0, u8, ZigZag>(&obj).unwrap();
+        assert_eq!(deserialize::<(i8, i8, i8), _, _, ZigZag>(bits.as_bitslice()).unwrap().0, obj);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct RegisterTest(UInt<3>, UInt<5>, UInt<12>, bool, UInt<64>);
+
+    #[test]
+    fn uint_test() {
+        let obj = RegisterTest(
+            UInt::new(5).unwrap(),
+            UInt::try_from(31u8).unwrap(),
+            UInt::try_from(0xABCu16).unwrap(),
+            true,
+            UInt::new(u64::MAX).unwrap(),
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 5 + 12 + 1 + 64);
+        assert_eq!(bits.as_slice()[0], 0b1111_1101);
+        assert_eq!(bits.as_slice()[1], 0xBC);
+        let (obj2, _) =
+            deserialize::<RegisterTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+        assert_eq!(u16::try_from(obj2.2).unwrap(), 0xABC);
+        assert_eq!(obj2.4.get(), u64::MAX);
+
+        assert!(matches!(UInt::<3>::new(8), Err(Error::Overflow)));
+        assert!(matches!(UInt::<12>::try_from(usize::MAX), Err(Error::Overflow)));
+        assert!(matches!(u8::try_from(obj2.2), Err(Error::Overflow)));
+        assert_eq!(UInt::<0>::MAX, 0);
+        assert_eq!(UInt::<12>::MAX, 0xFFF);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct AltitudeTest(Int<11>, Int<1>, Int<64>, Int<3>);
+
+    #[test]
+    fn int_test() {
+        let obj = AltitudeTest(
+            Int::new(-1024).unwrap(),
+            Int::try_from(-1i8).unwrap(),
+            Int::new(i64::MIN).unwrap(),
+            Int::try_from(3i32).unwrap(),
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 11 + 1 + 64 + 3);
+        assert_eq!(bits.as_slice()[0], 0);
+        assert_eq!(bits.as_slice()[1], 0b0000_1100);
+        let (obj2, _) =
+            deserialize::<AltitudeTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+        assert_eq!(i32::try_from(obj2.0).unwrap(), -1024);
+        assert_eq!(obj2.1.get(), -1);
+
+        let obj = AltitudeTest(
+            Int::new(1023).unwrap(),
+            Int::new(0).unwrap(),
+            Int::new(-5).unwrap(),
+            Int::new(-4).unwrap(),
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        let (obj2, _) =
+            deserialize::<AltitudeTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        assert!(matches!(Int::<11>::new(1024), Err(Error::Overflow)));
+        assert!(matches!(Int::<11>::new(-1025), Err(Error::Overflow)));
+        assert!(matches!(Int::<1>::try_from(1i64), Err(Error::Overflow)));
+        assert!(matches!(i8::try_from(obj2.0), Err(Error::Overflow)));
+        assert_eq!((Int::<3>::MIN, Int::<3>::MAX), (-4, 3));
+    }
+
+    const LOWEST: i64 = i64::MIN;
+    const HIGHEST: i64 = i64::MAX;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct RangedTest(Ranged<0, 37>, Ranged<-5, 120>, Ranged<7, 7>, Ranged<LOWEST, HIGHEST>);
+
+    #[test]
+    fn ranged_test() {
+        assert_eq!(Ranged::<0, 37>::BITS, 6);
+        assert_eq!(Ranged::<-5, 120>::BITS, 7);
+        assert_eq!(Ranged::<0, 128>::BITS, 8);
+        let obj = RangedTest(
+            Ranged::new(37).unwrap(),
+            Ranged::try_from(-5i8).unwrap(),
+            Ranged::new(7).unwrap(),
+            Ranged::new(i64::MIN).unwrap(),
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 6 + 7 + 64);
+        assert_eq!(bits.as_slice()[0], 37);
+        let (obj2, _) =
+            deserialize::<RangedTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+        assert_eq!(u8::try_from(obj2.0).unwrap(), 37);
+
+        assert!(matches!(Ranged::<0, 37>::new(38), Err(Error::Overflow)));
+        assert!(matches!(Ranged::<-5, 120>::new(-6), Err(Error::Overflow)));
+        assert!(matches!(Ranged::<-5, 120>::try_from(u64::MAX), Err(Error::Overflow)));
+        let negative = Ranged::<-5, 120>::new(-1).unwrap();
+        assert!(matches!(u8::try_from(negative), Err(Error::Overflow)));
+
+        // 38 fits the 6 bits but not the range
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&UInt::<6>::new(38).unwrap()).unwrap();
+        match deserialize::<Ranged<0, 37>, _, _, EndianEncoding>(bits.as_bitslice()) {
+            Err(Error::Message(_)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Opcode {
+        Nop,
+        Load(u8),
+        Store { addr: u16 },
+        Jump(i8, i8),
+        Halt,
+    }
+
+    impl Variants for Opcode {
+        const COUNT: usize = 5;
+    }
+
+    impl Variants for TestEnum {
+        const COUNT: usize = 2;
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct CompactTest(
+        CompactEnum<Opcode>,
+        CompactEnum<TestEnum>,
+        TestEnum,
+        Vec<CompactEnum<Opcode>>,
+    );
+
+    #[test]
+    fn compact_enum_test() {
+        let obj = CompactTest(
+            CompactEnum(Opcode::Halt),
+            CompactEnum(TestEnum::False(true)),
+            TestEnum::True(7),
+            vec![
+                CompactEnum(Opcode::Store { addr: 0xBEEF }),
+                CompactEnum(Opcode::Jump(-1, 1)),
+                CompactEnum(Opcode::Nop),
+            ],
+        );
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + (1 + 1) + (32 + 32) + 32 + (3 + 16) + (3 + 16) + 3);
+        assert_eq!(bits.as_slice()[0] & 0b111, 4);
+        let (obj2, _) =
+            deserialize::<CompactTest, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        // 6 fits the 3 bits but there are only 5 variants
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&UInt::<3>::new(6).unwrap()).unwrap();
+        match deserialize::<CompactEnum<Opcode>, _, _, EndianEncoding>(bits.as_bitslice()) {
+            Err(Error::InvalidVariant {
+                index: 6,
+                variants: 5,
+            }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Reply {
+        Ack,
+        Nack(u8),
+        Unknown(u8),
+    }
+
+    impl Discriminants for Reply {
+        const BITS: usize = 8;
+        const VALUES: &'static [u64] = &[0x7E, 0x81, 0xFF];
+        const FALLBACK: Option<u32> = Some(2);
+    }
+
+    impl Variants for Reply {
+        const COUNT: usize = 3;
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Strict {
+        On,
+        Off { delay: u16 },
+    }
+
+    impl Discriminants for Strict {
+        const BITS: usize = 4;
+        const VALUES: &'static [u64] = &[0xA, 0x5];
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Short {
+        Low,
+        High,
+    }
+
+    impl Discriminants for Short {
+        const BITS: usize = 2;
+        const VALUES: &'static [u64] = &[1];
+    }
+
+    #[test]
+    fn explicit_enum_test() {
+        let obj = vec![Explicit(Reply::Ack), Explicit(Reply::Nack(3))];
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.as_slice(), &[2, 0, 0, 0, 0x7E, 0x81, 3]);
+        let (obj2, _) =
+            deserialize::<Vec<Explicit<Reply>>, _, _, EndianEncoding>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let data = [0x42u8];
+        let (reply, _) =
+            deserialize::<Explicit<Reply>, _, _, EndianEncoding>(data.view_bits::<Lsb0>()).unwrap();
+        assert_eq!(reply, Explicit(Reply::Unknown(0x42)));
+        assert_eq!(
+            serialize::<_, Lsb0, u8, EndianEncoding>(&reply).unwrap().as_slice(),
+            &data
+        );
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Explicit(Reply::Unknown(0x7E))).is_err());
+
+        let obj = (Explicit(Strict::Off { delay: 0xBEEF }), Explicit(Strict::On));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 4 + 16 + 4);
+        let (obj2, _) = deserialize::<(Explicit<Strict>, Explicit<Strict>), _, _, EndianEncoding>(
+            bits.as_bitslice(),
+        )
+        .unwrap();
+        assert_eq!(obj, obj2);
+
+        let data = [0x0Fu8];
+        match deserialize::<Explicit<Strict>, _, _, EndianEncoding>(data.view_bits::<Lsb0>()) {
+            Err(Error::Message(_)) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // Short has no discriminant for its second variant
+        assert!(serialize::<_, Lsb0, u8, EndianEncoding>(&Explicit(Short::High)).is_err());
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&Explicit(Short::Low)).unwrap();
+        let (low, _) = deserialize::<Explicit<Short>, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!(low, Explicit(Short::Low));
+    }
+
+    /// 8 bit enum tags, 16 bit element counts and 12 bit payload lengths.
+    struct Protocol;
+
+    impl BinaryEncoding for Protocol {
+        fn deserialize_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+            deserializer: &mut BitDeserializer<O, S, EN>,
+        ) -> Result<usize>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            <EndianEncoding>::deserialize_len(deserializer)
+        }
+
+        fn serialize_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+            serializer: &mut BitSerializer<O, S, EN>,
+            len: usize,
+        ) -> Result<()>
+        where
+            BitSlice<O, S::Alias>: BitField,
+        {
+            <EndianEncoding>::serialize_len(serializer, len)
+        }
+
+        fn deserialize_seq_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+            deserializer: &mut BitDeserializer<O, S, EN>,
+        ) -> Result<usize>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            Ok(deserializer.load_bits::<u16>(16)? as usize)
+        }
+
+        fn serialize_seq_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+            serializer: &mut BitSerializer<O, S, EN>,
+            len: usize,
+        ) -> Result<()>
+        where
+            BitSlice<O, S::Alias>: BitField,
+        {
+            serializer.store_bits(u16::try_from(len).map_err(|_| Error::Overflow)?, 16);
+            Ok(())
+        }
+
+        fn deserialize_bytes_len<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+            deserializer: &mut BitDeserializer<O, S, EN>,
+        ) -> Result<usize>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            Ok(deserializer.load_bits::<u16>(12)? as usize)
+        }
+
+        fn serialize_bytes_len<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+            serializer: &mut BitSerializer<O, S, EN>,
+            len: usize,
+        ) -> Result<()>
+        where
+            BitSlice<O, S::Alias>: BitField,
+        {
+            serializer.store_bits(UInt::<12>::try_from(len)?.get(), 12);
+            Ok(())
+        }
+
+        fn deserialize_variant_index<O: BitOrder, S: BitStore, EN: BinaryEncoding>(
+            deserializer: &mut BitDeserializer<O, S, EN>,
+        ) -> Result<u32>
+        where
+            BitSlice<O, S>: BitField,
+        {
+            deserializer.load_bits(8)
+        }
+
+        fn serialize_variant_index<O: BitOrder + 'static, S: BitStore, EN: BinaryEncoding>(
+            serializer: &mut BitSerializer<O, S, EN>,
+            index: u32,
+        ) -> Result<()>
+        where
+            BitSlice<O, S::Alias>: BitField,
+        {
+            serializer.store_bits(u8::try_from(index).map_err(|_| Error::Overflow)?, 8);
+            Ok(())
+        }
+
+        delegate_primitive_encoding![
+            EndianEncoding;
+            i8, i16, i32, i64, i128, u16, u32, u64, u128, f32, f64
+        ];
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Packet {
+        kind: TestEnum,
+        samples: Vec<u8>,
+        name: String,
+    }
+
+    #[test]
+    fn split_len_test() {
+        let obj = Packet {
+            kind: TestEnum::True(1),
+            samples: vec![1, 2, 3],
+            name: "abc".to_owned(),
+        };
+        let bits = serialize::<_, Lsb0, u8, Protocol>(&obj).unwrap();
+        assert_eq!(bits.len(), (8 + 32) + (16 + 3 * 8) + (12 + 3 * 8));
+        let (obj2, _) = deserialize::<Packet, _, _, Protocol>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), (32 + 32) + (32 + 3 * 8) + (32 + 3 * 8));
+
+        let mut serializer = BitSerializer::<Lsb0, u8, Protocol>::new();
+        serde::Serializer::serialize_bytes(&mut serializer, &[0xAA; 5]).unwrap();
+        assert_eq!(serializer.vec.len(), 12 + 5 * 8);
+
+        let obj = Packet {
+            name: "x".repeat(4096),
+            ..obj
+        };
+        assert!(matches!(
+            serialize::<_, Lsb0, u8, Protocol>(&obj),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn len_width_test() {
+        let obj = VectorTest(1.104321, vec![true, false, true]);
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE, 8>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 8 + 3);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, EndianEncoding<BE, 8>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE, 16>>(&obj).unwrap();
+        assert_eq!(bits.as_slice()[8..10], [0, 3]);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, EndianEncoding<BE, 16>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<LE, 12>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 12 + 3);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, EndianEncoding<LE, 12>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<LE, 64>>(&obj).unwrap();
+        assert_eq!(bits.len(), 64 + 64 + 3);
+        let (obj2, _) =
+            deserialize::<VectorTest, _, _, EndianEncoding<LE, 64>>(bits.as_bitslice()).unwrap();
+        assert_eq!(obj, obj2);
+
+        // lengths are rejected instead of truncated
+        let obj = VectorTest(0.0, vec![false; 256]);
+        assert!(matches!(
+            serialize::<_, Lsb0, u8, EndianEncoding<LE, 8>>(&obj),
+            Err(Error::Overflow)
+        ));
+        let obj = VectorTest(0.0, vec![false; 4096]);
+        assert!(matches!(
+            serialize::<_, Lsb0, u8, EndianEncoding<LE, 12>>(&obj),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn options_test() {
+        let obj = IntTest(-2, 300, 7, u64::MAX);
+        let bits: BitVec<Lsb0, u8> = options().serialize(&obj).unwrap();
+        assert_eq!(bits, serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap());
+
+        let big = options().with_big_endian().with_varint_lengths();
+        let obj = VectorTest(1.5, vec![true, false]);
+        let bits: BitVec<Lsb0, u8> = big.serialize(&obj).unwrap();
+        type Encoding = VarintEncoding<EndianEncoding<BE>>;
+        assert_eq!(bits, serialize::<_, Lsb0, u8, Encoding>(&obj).unwrap());
+        let (obj2, read) = big.deserialize::<VectorTest, _, _>(bits.as_bitslice()).unwrap();
+        assert_eq!((obj, read), (obj2, bits.len()));
+
+        let data = [0x01u8, 0, 0, 0, 0xFF];
+        let (value, read) = options().deserialize::<u32, _, _>(data.view_bits::<Lsb0>()).unwrap();
+        assert_eq!((value, read), (1, 32));
+        let strict = options().with_trailing_bits(TrailingBits::Reject);
+        match strict.deserialize::<u32, _, _>(data.view_bits::<Lsb0>()) {
+            Err(Error::TrailingBits { remaining: 8 }) => {}
+            other => panic!("{:?}", other),
+        }
+        assert!(strict.deserialize::<u32, _, _>(data[..4].view_bits::<Lsb0>()).is_ok());
+
+        // a 2000 byte string that is really there, but over the limit
+        let mut data = vec![0xD0u8, 0x07, 0, 0];
+        data.resize(4 + 2000, b'a');
+        let limited = options().with_limit(1024);
+        match limited.deserialize::<String, _, _>(data.view_bits::<Lsb0>()) {
+            Err(Error::LimitExceeded { limit: 1024, .. }) => {}
+            other => panic!("{:?}", other),
+        }
+        let short = options().with_max_len(3);
+        match short.deserialize::<Vec<u8>, _, _>(data.view_bits::<Lsb0>()) {
+            Err(Error::LimitExceeded { limit: 3, requested: 2000 }) => {}
+            other => panic!("{:?}", other),
+        }
+        let data = [3u8, 0, 0, 0, b'a', b'b', b'c'];
+        let (value, _) = short.deserialize::<Vec<u8>, _, _>(data.view_bits::<Lsb0>()).unwrap();
+        assert_eq!(value, b"abc");
+
+        let obj = (true, 0x1234u16, false);
+        for order in [options(), options().with_msb_first()].iter() {
+            let (bytes, len) = order.to_bytes(&obj).unwrap();
+            assert_eq!((bytes.len(), len), (3, 18));
+            assert_eq!(order.from_bytes(&bytes, len).unwrap(), (obj, len));
+        }
+        let flags = (true, false, true);
+        assert_eq!(options().to_bytes(&flags).unwrap(), (vec![0x05], 3));
+        assert_eq!(options().with_msb_first().to_bytes(&flags).unwrap(), (vec![0xA0], 3));
+        assert!(options().from_bytes::<u16>(&[0xFF], 16).is_err());
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct MixedEndianTest(u16, BigEndian<u32>, LittleEndian<i16>, BigEndian<f32>);
+
+    #[test]
+    fn mixed_endian_test() {
+        let obj = MixedEndianTest(0x0102, BigEndian(0x0304_0506), LittleEndian(-2), BigEndian(1.0));
+        let expected = [0x02u8, 0x01, 0x03, 0x04, 0x05, 0x06, 0xFE, 0xFF, 0x3F, 0x80, 0, 0];
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.as_raw_slice(), &expected);
+        let (obj2, _) = deserialize::<MixedEndianTest, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!(obj, obj2);
+
+        // the wrapped fields keep their byte order under a big endian encoding
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE>>(&obj).unwrap();
+        assert_eq!(&bits.as_raw_slice()[..2], &[0x01, 0x02]);
+        assert_eq!(&bits.as_raw_slice()[2..], &expected[2..]);
+        let (obj2, _) = deserialize::<MixedEndianTest, _, _, EndianEncoding<BE>>(&bits).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Payload(bool, UInt<3>, u16);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct OrderTest(u8, Msb<Payload>, bool, Msb<u8>, Lsb<UInt<4>>);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct NestedOrderTest(UInt<3>, ChecksumStart, u8, Msb<(bool, Align<8>, u16, Crc<Crc8>)>);
+
+    #[test]
+    fn order_test() {
+        let payload = Payload(true, UInt::new(5).unwrap(), 0x1234);
+        let obj = OrderTest(0xA5, Msb(payload), true, Msb(0x12), Lsb(UInt::new(3).unwrap()));
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 8 + 20 + 1 + 8 + 4);
+
+        // the wrapped values take their own width, laid out exactly as in a Msb0 stream
+        let payload = Payload(true, UInt::new(5).unwrap(), 0x1234);
+        let expected = (0xA5u8, payload, true, 0x12u8);
+        let expected = serialize::<_, Msb0, u8, EndianEncoding>(&expected).unwrap();
+        assert_eq!(&bits[..37], expected.as_bitslice());
+        assert_eq!(bits[37..].load_le::<u8>(), 3);
+        let msb = serialize::<_, Msb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(&msb[..37], expected.as_bitslice());
+
+        let (obj2, read) = deserialize::<OrderTest, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!((&obj, read), (&obj2, bits.len()));
+
+        // only the bytes the wrapped values cover are fetched and charged against the limit
+        let mut data = bits.clone();
+        data.resize(8 * 4096, false);
+        let mut limited = BitDeserializer::<Lsb0, u8, EndianEncoding>::new(&data).with_limit(7);
+        assert_eq!(OrderTest::deserialize(&mut limited).unwrap(), obj);
+        let mut limited = BitDeserializer::<Lsb0, u8, EndianEncoding>::new(&data).with_limit(6);
+        match OrderTest::deserialize(&mut limited) {
+            Err(Error::LimitExceeded { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        // errors point into the outer stream
+        match deserialize::<OrderTest, _, _, EndianEncoding>(&bits[..20]) {
+            Err(Error::UnexpectedEof { offset: 12, .. }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        let many: Vec<(bool, Msb<u16>)> = (0..1000).map(|i| (i % 3 == 0, Msb(i))).collect();
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&many).unwrap();
+        let (many2, _) = deserialize::<Vec<(bool, Msb<u16>)>, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!(many, many2);
+
+        // alignment inside a wrapped value counts from the start of the stream
+        let obj = NestedOrderTest(
+            UInt::new(6).unwrap(),
+            ChecksumStart,
+            0x5A,
+            Msb((true, Align, 0xBEEF, Crc::new())),
+        );
+        let mut bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 8 + 1 + 4 + 16 + 8);
+
+        // and a checksum inside it covers the bits in front of the wrapper
+        let crc = bits[32..].iter().fold(0u8, |crc, bit| crc << 1 | *bit as u8);
+        assert_eq!(crc, Crc8::compute(&bits[3..32]));
+        let (obj2, read) = deserialize::<NestedOrderTest, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!(((obj2.3).0 .3 .0, read), (crc, bits.len()));
+
+        let flipped = !bits[5];
+        bits.set(5, flipped);
+        match deserialize::<NestedOrderTest, _, _, EndianEncoding>(&bits) {
+            Err(Error::ChecksumMismatch { offset: 32, .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct PackedTest(bool, bool, bool, u16, u8, i16);
+
+    #[test]
+    fn packed_test() {
+        let obj = PackedTest(true, false, true, 0xABCD, 0x5A, -2);
+        let mut bits = serialize::<_, Msb0, u8, PackedEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 16 + 8 + 16);
+        let (obj2, read) = deserialize::<PackedTest, _, _, PackedEncoding>(&bits).unwrap();
+        assert_eq!((&obj, read), (&obj2, bits.len()));
+        bits.resize(48, false);
+        assert_eq!(bits.as_raw_slice(), &[0xB5, 0x79, 0xAB, 0x5F, 0xFF, 0xC0]);
+
+        // fields of a few bits go most significant bit first too
+        type Fields = (UInt<5>, Explicit<Strict>, CompactEnum<Reply>);
+        let reply = CompactEnum(Reply::Nack(0xFF));
+        let fields = (UInt::new(0b10110).unwrap(), Explicit(Strict::On), reply);
+        let mut bits = serialize::<_, Msb0, u8, PackedEncoding>(&fields).unwrap();
+        assert_eq!(bits.len(), 5 + 4 + 2 + 8);
+        let (fields2, _) = deserialize::<Fields, _, _, PackedEncoding>(&bits).unwrap();
+        assert_eq!(fields, fields2);
+        bits.resize(24, false);
+        assert_eq!(bits.as_raw_slice(), &[0xB5, 0x3F, 0xE0]);
+
+        // the bit sequence is the same in a Lsb0 stream, only the bytes differ
+        let bits = serialize::<_, Lsb0, u8, PackedEncoding>(&obj).unwrap();
+        assert_eq!(bits[3..19].iter().fold(0u16, |v, b| v << 1 | *b as u16), 0xABCD);
+        let (obj2, _) = deserialize::<PackedTest, _, _, PackedEncoding>(&bits).unwrap();
+        assert_eq!(obj, obj2);
+
+        // a five bit length in front of values that straddle byte boundaries
+        let obj = VectorTest(-0.5, vec![true, false]);
+        let bits = serialize::<_, Msb0, u8, PackedEncoding<5>>(&obj).unwrap();
+        assert_eq!(bits[..64].iter().fold(0u64, |v, b| v << 1 | *b as u64), (-0.5f64).to_bits());
+        assert_eq!(bits[64..69].iter().fold(0u8, |v, b| v << 1 | *b as u8), 2);
+        let (obj2, _) = deserialize::<VectorTest, _, _, PackedEncoding<5>>(&bits).unwrap();
+        assert_eq!(obj, obj2);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct PaddingTest(bool, Align<8>, u8, Padding<3>, bool, Align<16>);
+
+    #[test]
+    fn padding_test() {
+        let obj = PaddingTest(true, Align, 0x42, Padding, true, Align);
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.as_raw_slice(), &[0x01, 0x42, 0x08, 0x00]);
+        let mut deserializer =
+            BitDeserializer::<Lsb0, u8, EndianEncoding>::new(&bits).with_padding_check();
+        assert_eq!(PaddingTest::deserialize(&mut deserializer).unwrap(), obj);
+        assert_eq!(deserializer.offset, 32);
+
+        let filled = options().with_padding_fill(0xFF);
+        let bits: BitVec<Lsb0, u8> = filled.serialize(&obj).unwrap();
+        assert_eq!(bits.as_raw_slice(), &[0xFF, 0x42, 0xFF, 0xFF]);
+        let (obj2, read) = options().deserialize::<PaddingTest, _, _>(&bits).unwrap();
+        assert_eq!((&obj, read), (&obj2, 32));
+        match options().with_padding_check().deserialize::<PaddingTest, _, _>(&bits) {
+            Err(Error::InvalidPadding { offset: 1 }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct ConstTest(Const<u32, 0xCAFE_BABE>, Const<u8, 2>, Const<i16, 0xFFFE>, u8);
+
+    #[test]
+    fn const_test() {
+        let obj = ConstTest(Const::new(), Const::new(), Const::new(), 7);
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE>>(&obj).unwrap();
+        assert_eq!(bits.as_raw_slice(), &[0xCA, 0xFE, 0xBA, 0xBE, 0x02, 0xFF, 0xFE, 0x07]);
+        let (obj2, _) = deserialize::<ConstTest, _, _, EndianEncoding<BE>>(&bits).unwrap();
+        assert_eq!(obj, obj2);
+
+        let data = [0xCAu8, 0xFE, 0xBA, 0xBE, 0x03, 0xFF, 0xFE, 0x07];
+        match deserialize::<ConstTest, _, _, EndianEncoding<BE>>(data.view_bits::<Lsb0>()) {
+            Err(Error::ConstMismatch { expected: 2, actual: 3, offset: 32 }) => {}
+            other => panic!("{:?}", other),
+        }
+        let data = [0xCAu8, 0xFE, 0xBA, 0xBE, 0x02, 0x7F, 0xFE, 0x07];
+        match deserialize::<ConstTest, _, _, EndianEncoding<BE>>(data.view_bits::<Lsb0>()) {
+            Err(Error::ConstMismatch { expected: 0xFFFE, actual: 0x7FFE, offset: 40 }) => {}
+            other => panic!("{:?}", other),
+        }
+
+        type Magic = Const<u64, 0x1234_5678_9ABC_DEF0>;
+        let bits = serialize::<_, Lsb0, u8, EndianEncoding<BE>>(&Magic::new()).unwrap();
+        assert_eq!(bits.as_raw_slice(), &[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]);
+        let data = [0x12u8, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF1];
+        match deserialize::<Magic, _, _, EndianEncoding<BE>>(data.view_bits::<Lsb0>()) {
+            Err(Error::ConstMismatch { expected: 0x1234_5678_9ABC_DEF0, actual, offset: 0 }) => {
+                assert_eq!(actual, 0x1234_5678_9ABC_DEF1)
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct ChecksumTest(UInt<3>, ChecksumStart, bool, u16, UInt<5>, Crc<Crc16>, Crc<Parity>);
+
+    #[test]
+    fn checksum_test() {
+        let check = b"123456789";
+        assert_eq!(Crc8::compute(check.view_bits::<Msb0>()), 0xF4);
+        assert_eq!(Crc16::compute(check.view_bits::<Msb0>()), 0x29B1);
+        assert_eq!(Crc32::compute(check.view_bits::<Lsb0>()), 0xCBF4_3926);
+        assert_eq!(Sum8::compute(check.view_bits::<Msb0>()), 0xDD);
+        assert!(Parity::compute(check.view_bits::<Lsb0>()));
+
+        let obj = ChecksumTest(
+            UInt::new(5).unwrap(),
+            ChecksumStart,
+            true,
+            0xBEEF,
+            UInt::new(17).unwrap(),
+            Crc::new(),
+            Crc::new(),
+        );
+        let mut bits = serialize::<_, Lsb0, u8, EndianEncoding>(&obj).unwrap();
+        assert_eq!(bits.len(), 3 + 1 + 16 + 5 + 16 + 1);
+        let (obj2, _) = deserialize::<ChecksumTest, _, _, EndianEncoding>(&bits).unwrap();
+        assert_eq!((obj2.5).0, Crc16::compute(&bits[3..25]));
+        assert_eq!(bits[3..].count_ones() % 2, 0);
+
+        // the bits in front of the range are not covered, a flip inside it is caught
+        bits.set(0, false);
+        assert!(deserialize::<ChecksumTest, _, _, EndianEncoding>(&bits).is_ok());
+        let flipped = !bits[10];
+        bits.set(10, flipped);
+        match deserialize::<ChecksumTest, _, _, EndianEncoding>(&bits) {
+            Err(Error::ChecksumMismatch { offset: 25, .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct BooleanTest(Vec<bool>);
 
@@ -0,0 +1,269 @@
+use bitvec::field::BitField;
+use bitvec::order::{BitOrder, Lsb0, Msb0};
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use bitvec::view::BitView;
+use byteorder::{BE, LE};
+use serde::{Deserialize, Serialize};
+
+use crate::de::BitDeserializer;
+use crate::encoding::{BinaryEncoding, EndianEncoding, VarintEncoding};
+use crate::ser::BitSerializer;
+use crate::{Error, Result};
+
+/// What deserialization does with bits left over after the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingBits {
+    Allow,
+    Reject,
+}
+
+/// Serialization settings chosen at runtime, each combination maps onto one of the encodings.
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    msb_first: bool,
+    big_endian: bool,
+    varint_lengths: bool,
+    limit: Option<usize>,
+    max_len: Option<usize>,
+    trailing_bits: TrailingBits,
+    padding_fill: u8,
+    check_padding: bool,
+    canonical_maps: bool,
+}
+
+/// Starts from `Lsb0` bytes, little endian values, 32 bit lengths, no allocation or length limit,
+/// trailing bits allowed and unchecked zero padding. Map entries keep their iteration order.
+pub fn options() -> Options {
+    Options::default()
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            msb_first: false,
+            big_endian: false,
+            varint_lengths: false,
+            limit: None,
+            max_len: None,
+            trailing_bits: TrailingBits::Allow,
+            padding_fill: 0,
+            check_padding: false,
+            canonical_maps: false,
+        }
+    }
+}
+
+impl Options {
+    /// Fills each byte from its most significant bit in `to_bytes` and `from_bytes`, as `Msb0`.
+    pub fn with_msb_first(mut self) -> Self {
+        self.msb_first = true;
+        self
+    }
+
+    /// Fills each byte from its least significant bit in `to_bytes` and `from_bytes`, as `Lsb0`.
+    pub fn with_lsb_first(mut self) -> Self {
+        self.msb_first = false;
+        self
+    }
+
+    /// Writes fixed width values most significant byte first.
+    pub fn with_big_endian(mut self) -> Self {
+        self.big_endian = true;
+        self
+    }
+
+    /// Writes fixed width values least significant byte first.
+    pub fn with_little_endian(mut self) -> Self {
+        self.big_endian = false;
+        self
+    }
+
+    /// Writes lengths and enum variant indices as LEB128, see `VarintEncoding`.
+    pub fn with_varint_lengths(mut self) -> Self {
+        self.varint_lengths = true;
+        self
+    }
+
+    /// Writes lengths and enum variant indices as 32 bit values.
+    pub fn with_fixed_lengths(mut self) -> Self {
+        self.varint_lengths = false;
+        self
+    }
+
+    /// Limits the total number of bytes allocated while deserializing, see
+    /// `BitDeserializer::with_limit`.
+    pub fn with_limit(mut self, bytes: usize) -> Self {
+        self.limit = Some(bytes);
+        self
+    }
+
+    /// Lifts the allocation limit.
+    pub fn with_no_limit(mut self) -> Self {
+        self.limit = None;
+        self
+    }
+
+    /// Limits the length of every sequence, map, byte buffer and string, see
+    /// `BitDeserializer::with_max_len`.
+    pub fn with_max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self
+    }
+
+    /// Lifts the length limit.
+    pub fn with_no_max_len(mut self) -> Self {
+        self.max_len = None;
+        self
+    }
+
+    /// Chooses whether bits left over after the value are an error.
+    pub fn with_trailing_bits(mut self, trailing_bits: TrailingBits) -> Self {
+        self.trailing_bits = trailing_bits;
+        self
+    }
+
+    /// Fills padding from `pattern`, see `BitSerializer::with_padding_fill`.
+    pub fn with_padding_fill(mut self, pattern: u8) -> Self {
+        self.padding_fill = pattern;
+        self
+    }
+
+    /// Rejects padding that is not zero, see `BitDeserializer::with_padding_check`.
+    pub fn with_padding_check(mut self) -> Self {
+        self.check_padding = true;
+        self
+    }
+
+    /// Sorts map entries by their serialized keys, see `BitSerializer::with_canonical_maps`.
+    pub fn with_canonical_maps(mut self) -> Self {
+        self.canonical_maps = true;
+        self
+    }
+
+    /// Serializes a value with the chosen settings.
+    pub fn serialize<T: Serialize + ?Sized, O: BitOrder + 'static, S: BitStore>(
+        &self,
+        value: &T,
+    ) -> Result<BitVec<O, S>>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        match (self.big_endian, self.varint_lengths) {
+            (false, false) => self.serialize_with::<_, _, _, EndianEncoding<LE>>(value),
+            (true, false) => self.serialize_with::<_, _, _, EndianEncoding<BE>>(value),
+            (false, true) => {
+                self.serialize_with::<_, _, _, VarintEncoding<EndianEncoding<LE>>>(value)
+            }
+            (true, true) => {
+                self.serialize_with::<_, _, _, VarintEncoding<EndianEncoding<BE>>>(value)
+            }
+        }
+    }
+
+    /// Deserializes a value, returning it along with the number of bits read.
+    pub fn deserialize<'de, T: Deserialize<'de>, O: BitOrder, S: BitStore>(
+        &self,
+        bits: &'de BitSlice<O, S>,
+    ) -> Result<(T, usize)>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        match (self.big_endian, self.varint_lengths) {
+            (false, false) => self.deserialize_with::<_, _, _, EndianEncoding<LE>>(bits),
+            (true, false) => self.deserialize_with::<_, _, _, EndianEncoding<BE>>(bits),
+            (false, true) => {
+                self.deserialize_with::<_, _, _, VarintEncoding<EndianEncoding<LE>>>(bits)
+            }
+            (true, true) => {
+                self.deserialize_with::<_, _, _, VarintEncoding<EndianEncoding<BE>>>(bits)
+            }
+        }
+    }
+
+    /// Serializes a value into bytes in the chosen bit order, returning them along with the number
+    /// of bits written. The last byte is padded with zeros.
+    pub fn to_bytes<T: Serialize + ?Sized>(&self, value: &T) -> Result<(Vec<u8>, usize)> {
+        fn into_bytes<O: BitOrder>(mut bits: BitVec<O, u8>) -> (Vec<u8>, usize)
+        where
+            BitSlice<O, u8>: BitField,
+        {
+            let len = bits.len();
+            bits.resize(len.next_multiple_of(8), false);
+            (bits.into_vec(), len)
+        }
+
+        if self.msb_first {
+            self.serialize::<_, Msb0, u8>(value).map(into_bytes)
+        } else {
+            self.serialize::<_, Lsb0, u8>(value).map(into_bytes)
+        }
+    }
+
+    /// Deserializes a value from the first `bits` bits of `bytes` in the chosen bit order,
+    /// returning it along with the number of bits read.
+    pub fn from_bytes<'de, T: Deserialize<'de>>(
+        &self,
+        bytes: &'de [u8],
+        bits: usize,
+    ) -> Result<(T, usize)> {
+        if bits > bytes.len() * 8 {
+            return Err(Error::UnexpectedEof {
+                needed_bits: bits,
+                available_bits: bytes.len() * 8,
+                offset: 0,
+            });
+        }
+        if self.msb_first {
+            self.deserialize(&bytes.view_bits::<Msb0>()[..bits])
+        } else {
+            self.deserialize(&bytes.view_bits::<Lsb0>()[..bits])
+        }
+    }
+
+    fn serialize_with<
+        T: Serialize + ?Sized,
+        O: BitOrder + 'static,
+        S: BitStore,
+        E: BinaryEncoding,
+    >(
+        &self,
+        value: &T,
+    ) -> Result<BitVec<O, S>>
+    where
+        BitSlice<O, S::Alias>: BitField,
+    {
+        let mut serializer = BitSerializer::<O, S, E>::new().with_padding_fill(self.padding_fill);
+        if self.canonical_maps {
+            serializer = serializer.with_canonical_maps();
+        }
+        value.serialize(&mut serializer)?;
+        Ok(serializer.vec)
+    }
+
+    fn deserialize_with<'de, T: Deserialize<'de>, O: BitOrder, S: BitStore, E: BinaryEncoding>(
+        &self,
+        bits: &'de BitSlice<O, S>,
+    ) -> Result<(T, usize)>
+    where
+        BitSlice<O, S>: BitField,
+    {
+        let mut deserializer = BitDeserializer::<O, S, E>::new(bits);
+        if let Some(limit) = self.limit {
+            deserializer = deserializer.with_limit(limit);
+        }
+        if let Some(len) = self.max_len {
+            deserializer = deserializer.with_max_len(len);
+        }
+        if self.check_padding {
+            deserializer = deserializer.with_padding_check();
+        }
+        let value = T::deserialize(&mut deserializer)?;
+        let remaining = bits.len() - deserializer.offset;
+        if self.trailing_bits == TrailingBits::Reject && remaining > 0 {
+            return Err(Error::TrailingBits { remaining });
+        }
+        Ok((value, deserializer.offset))
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The newtype struct name `Msb` is serialized under.
+pub(crate) const MSB_FIRST: &str = "\0bitserde::Msb";
+
+/// The newtype struct name `Lsb` is serialized under.
+pub(crate) const LSB_FIRST: &str = "\0bitserde::Lsb";
+
+/// Serializes the wrapped value as if the stream were `Msb0`, whatever the surrounding order.
+///
+/// The value takes exactly its own bits, in the order serializing it at the same position of a
+/// `BitVec<Msb0, u8>` gives them, so a byte aligned `Msb<u16>` in a `Lsb0` stream goes most
+/// significant bit of each byte first and `Msb<bool>` is a single bit. `Align` and checksums inside the value still
+/// count from the start of the whole stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Msb<T>(pub T);
+
+/// Serializes the wrapped value as if the stream were `Lsb0`, see `Msb`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lsb<T>(pub T);
+
+/// The bits of the stream a `Msb` or `Lsb` value is nested in, in stream order.
+pub(crate) trait BitSource {
+    /// The position just past the last bit.
+    fn end(&self) -> usize;
+
+    /// Feeds the bits at the positions in `range` to `push`.
+    fn for_each_bit(&self, range: Range<usize>, push: &mut dyn FnMut(bool));
+}
+
+impl<O: BitOrder, S: BitStore> BitSource for &BitSlice<O, S> {
+    fn end(&self) -> usize {
+        self.len()
+    }
+
+    fn for_each_bit(&self, range: Range<usize>, push: &mut dyn FnMut(bool)) {
+        self[range].iter().for_each(|bit| push(*bit));
+    }
+}
+
+/// Visits the newtype struct an order wrapper is serialized as.
+struct OrderVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for OrderVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a value in a different bit order")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+macro_rules! impl_order_wrapper {
+    ($($wrapper:ident => $name:ident),*) => {
+        $(
+            impl<T: Serialize> Serialize for $wrapper<T> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serializer.serialize_newtype_struct($name, &self.0)
+                }
+            }
+
+            impl<'de, T: Deserialize<'de>> Deserialize<'de> for $wrapper<T> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    deserializer
+                        .deserialize_newtype_struct($name, OrderVisitor(PhantomData))
+                        .map($wrapper)
+                }
+            }
+        )*
+    };
+}
+
+impl_order_wrapper![Msb => MSB_FIRST, Lsb => LSB_FIRST];
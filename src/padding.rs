@@ -0,0 +1,67 @@
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+use serde::de::{SeqAccess, Visitor};
+use serde::ser::SerializeTupleStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The tuple struct name `Align` is serialized under, its length carries the alignment in bits.
+pub(crate) const ALIGN: &str = "\0bitserde::Align";
+
+/// The tuple struct name `Padding` is serialized under, its length carries the number of bits.
+pub(crate) const PADDING: &str = "\0bitserde::Padding";
+
+/// Pads the stream to the next multiple of `BITS` bits, counted from the start of the stream.
+///
+/// `Align<8>` moves to the next byte boundary, `Align<32>` to the next 32 bit word. The padding
+/// bits are filled with `BitSerializer::with_padding_fill` and skipped on deserialization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Align<const BITS: usize>;
+
+/// Exactly `BITS` padding bits, filled and skipped like the padding of `Align`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Padding<const BITS: usize>;
+
+/// Visits the empty tuple struct a padding marker is serialized as.
+struct MarkerVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Default> Visitor<'de> for MarkerVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("padding")
+    }
+
+    fn visit_seq<A>(self, _seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(T::default())
+    }
+}
+
+macro_rules! impl_padding_marker {
+    ($($marker:ident => $name:ident),*) => {
+        $(
+            impl<const BITS: usize> Serialize for $marker<BITS> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serializer.serialize_tuple_struct($name, BITS)?.end()
+                }
+            }
+
+            impl<'de, const BITS: usize> Deserialize<'de> for $marker<BITS> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    deserializer.deserialize_tuple_struct($name, BITS, MarkerVisitor(PhantomData))
+                }
+            }
+        )*
+    };
+}
+
+impl_padding_marker![Align => ALIGN, Padding => PADDING];
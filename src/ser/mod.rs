@@ -1,9 +1,9 @@
-use std::fmt::Display;
 use std::io::Write;
 use std::marker::PhantomData;
 
 use bitvec::field::BitField;
-use bitvec::order::{BitOrder, Lsb0};
+use bitvec::mem::BitMemory;
+use bitvec::order::{BitOrder, Lsb0, Msb0};
 use bitvec::slice::BitSlice;
 use bitvec::store::BitStore;
 use bitvec::vec::BitVec;
@@ -14,8 +14,13 @@ use serde::ser::{
 };
 use serde::{Serialize, Serializer};
 
+use crate::checksum::{Checksum, Crc16, Crc32, Crc8, Parity, Sum8, CHECKSUM_START};
+use crate::container::FIELD_BITS;
 use crate::encoding::{BinaryEncoding, EndianEncoding};
 use crate::error::Error::Unsupported;
+use crate::order::{LSB_FIRST, MSB_FIRST};
+use crate::padding::{ALIGN, PADDING};
+use crate::variant::{variant_bits, COMPACT_ENUM, KNOWN_VARIANT};
 use crate::Error;
 
 pub struct BitSerializer<O = Lsb0, T = usize, E = EndianEncoding>
@@ -26,6 +31,162 @@ where
 {
     pub vec: BitVec<O, T>,
     pub(crate) endian: PhantomData<E>,
+    canonical_maps: bool,
+    padding_fill: u8,
+    checksum_start: usize,
+    compact_variants: Option<usize>,
+    known_variant: bool,
+    field_bits: Option<usize>,
+    base: usize,
+    needs_earlier_bits: bool,
+}
+
+impl<O: BitOrder, S: BitStore, E: BinaryEncoding> BitSerializer<O, S, E>
+where
+    BitSlice<O, S::Alias>: BitField,
+{
+    pub fn new() -> Self {
+        BitSerializer {
+            vec: BitVec::new(),
+            endian: PhantomData,
+            canonical_maps: false,
+            padding_fill: 0,
+            checksum_start: 0,
+            compact_variants: None,
+            known_variant: false,
+            field_bits: None,
+            base: 0,
+            needs_earlier_bits: false,
+        }
+    }
+
+    /// Sorts map entries by their serialized keys so that equal maps produce identical bits.
+    pub fn with_canonical_maps(mut self) -> Self {
+        self.canonical_maps = true;
+        self
+    }
+
+    /// Fills `Align` and `Padding` bits from `pattern`, the bit at stream position `i` taking bit
+    /// `i % 8` of it. Padding is zero by default.
+    pub fn with_padding_fill(mut self, pattern: u8) -> Self {
+        self.padding_fill = pattern;
+        self
+    }
+
+    /// The position of the next bit in the whole stream, which only differs from the length of
+    /// `vec` inside a `Msb` or `Lsb` value.
+    #[inline]
+    fn position(&self) -> usize {
+        self.base + self.vec.len()
+    }
+
+    /// Appends `count` padding bits.
+    #[inline]
+    fn pad(&mut self, count: usize) {
+        let start = self.position();
+        let pattern = self.padding_fill;
+        self.vec
+            .extend((start..start + count).map(|position| pattern >> (position % 8) & 1 == 1));
+    }
+
+    /// Appends the `count` least significant bits of `value` to the bit vector.
+    #[inline]
+    pub(crate) fn store_bits<M: BitMemory>(&mut self, value: M, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let len = self.vec.len();
+        self.vec.resize(len + count, false);
+        self.vec[len..].split_at_mut(0).1.store_le(value);
+    }
+}
+
+impl<O: BitOrder + 'static, S: BitStore, E: BinaryEncoding> BitSerializer<O, S, E>
+where
+    BitSlice<O, S::Alias>: BitField,
+{
+    /// Writes an enum discriminant, in the fewest bits if a `CompactEnum` announced the count and
+    /// not at all if an `Explicit` already wrote it.
+    #[inline]
+    fn serialize_variant_index(&mut self, index: u32) -> Result<(), Error> {
+        if std::mem::take(&mut self.known_variant) {
+            return Ok(());
+        }
+        match self.compact_variants.take() {
+            Some(variants) if index as usize >= variants => Err(Error::InvalidVariant {
+                index: index as usize,
+                variants,
+            }),
+            Some(variants) => E::encode_bits(self, index as u64, variant_bits(variants)),
+            None => E::serialize_variant_index(self, index),
+        }
+    }
+
+    /// Serializes `value` in bit order `P` and appends its bits in order.
+    ///
+    /// The nested stream starts with the bits since the last byte boundary so that it lays the
+    /// value out as a `P` stream would at the same position, and `Align` inside it counts from the
+    /// start of the whole stream. A checksum inside it that covers bits before it makes it start
+    /// over from the byte holding the `ChecksumStart`.
+    fn serialize_in_order<P: BitOrder + 'static, T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        BitSlice<P, <u8 as BitStore>::Alias>: BitField,
+    {
+        let position = self.position();
+        let mut start = position - position % 8;
+        loop {
+            let mut inner = BitSerializer::<P, u8, E>::new();
+            inner
+                .vec
+                .extend(self.vec[start - self.base..].iter().copied());
+            inner.base = start;
+            inner.canonical_maps = self.canonical_maps;
+            inner.padding_fill = self.padding_fill;
+            inner.checksum_start = self.checksum_start;
+            inner.compact_variants = self.compact_variants;
+            inner.known_variant = self.known_variant;
+            value.serialize(&mut inner)?;
+            let checksum_start = self.checksum_start;
+            if inner.needs_earlier_bits && (self.base..start).contains(&checksum_start) {
+                start = checksum_start - checksum_start % 8;
+                continue;
+            }
+            self.needs_earlier_bits |= inner.needs_earlier_bits;
+            self.compact_variants = None;
+            self.known_variant = false;
+            self.vec
+                .extend(inner.vec[position - start..].iter().copied());
+            self.checksum_start = inner.checksum_start;
+            return Ok(());
+        }
+    }
+
+    /// Writes the checksum `A` over the bits since the last `ChecksumStart`.
+    fn serialize_checksum<A: Checksum>(&mut self) -> Result<(), Error> {
+        let start = self.checksum_start.min(self.position());
+        if start < self.base {
+            // inside a `Msb` or `Lsb` value that does not hold the bits from `start` yet
+            self.needs_earlier_bits = true;
+        }
+        let value = A::compute(
+            self.vec[start.max(self.base) - self.base..]
+                .split_at_mut(0)
+                .1,
+        );
+        value.serialize(self)
+    }
+}
+
+impl<O: BitOrder, S: BitStore, E: BinaryEncoding> Default for BitSerializer<O, S, E>
+where
+    BitSlice<O, S::Alias>: BitField,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct Compound<'a, O: BitOrder, S: BitStore, E: BinaryEncoding> {
@@ -38,8 +199,7 @@ macro_rules! impl_encoding_serialization {
             $(
                 #[inline]
                 fn [<serialize_ $type>](self, v: $type) -> Result<Self::Ok, Self::Error> {
-                    E::[<serialize_ $type>](&mut self.vec, v)?;
-                    Ok(())
+                    E::[<encode_ $type>](self, v)
                 }
             )*
         }
@@ -67,35 +227,42 @@ where
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.vec.write(&[v])?;
-        Ok(())
+        E::encode_u8(self, v)
     }
 
-    impl_encoding_serialization![i8, i16, i32, i64, u16, u32, u64, f32, f64];
+    impl_encoding_serialization![i8, i16, i32, i64, i128, u16, u32, u128, f32, f64];
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        if let Some(count) = self.field_bits.take() {
+            return E::encode_bits(self, v, count);
+        }
+        E::encode_u64(self, v)
+    }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        Err(Unsupported)
+        E::serialize_char(self, v)
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Err(Unsupported)
+        E::serialize_str(self, v)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        E::serialize_len(self, v.len())?;
-        self.vec.write(v)?;
+        E::serialize_bytes_len(self, v.len())?;
+        self.vec.write_all(v)?;
         Ok(())
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Err(Unsupported)
+        E::serialize_presence(self, false)
     }
 
     fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: Serialize,
     {
-        Err(Unsupported)
+        E::serialize_presence(self, true)?;
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
@@ -103,6 +270,9 @@ where
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        if name == CHECKSUM_START {
+            self.checksum_start = self.position();
+        }
         Ok(())
     }
 
@@ -112,7 +282,7 @@ where
         variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        E::serialize_len(self, variant_index as usize)?;
+        self.serialize_variant_index(variant_index)?;
         Ok(())
     }
 
@@ -124,7 +294,16 @@ where
     where
         T: Serialize,
     {
-        value.serialize(self)
+        match name {
+            MSB_FIRST => self.serialize_in_order::<Msb0, T>(value),
+            LSB_FIRST => self.serialize_in_order::<Lsb0, T>(value),
+            Crc8::NAME => self.serialize_checksum::<Crc8>(),
+            Crc16::NAME => self.serialize_checksum::<Crc16>(),
+            Crc32::NAME => self.serialize_checksum::<Crc32>(),
+            Parity::NAME => self.serialize_checksum::<Parity>(),
+            Sum8::NAME => self.serialize_checksum::<Sum8>(),
+            _ => value.serialize(self),
+        }
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -137,13 +316,13 @@ where
     where
         T: Serialize,
     {
-        E::serialize_len(self, variant_index as usize)?;
+        self.serialize_variant_index(variant_index)?;
         value.serialize(self)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
         if let Some(size) = len {
-            E::serialize_len(self, size)?;
+            E::serialize_seq_len(self, size)?;
         }
         Ok(Compound { ser: self })
     }
@@ -157,6 +336,20 @@ where
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        if name == FIELD_BITS {
+            self.field_bits = Some(len);
+        } else if name == COMPACT_ENUM {
+            self.compact_variants = Some(len);
+        } else if name == KNOWN_VARIANT {
+            self.known_variant = true;
+        } else if name == ALIGN {
+            let misalignment = self.position().checked_rem(len).unwrap_or(0);
+            if misalignment > 0 {
+                self.pad(len - misalignment);
+            }
+        } else if name == PADDING {
+            self.pad(len);
+        }
         Ok(Compound { ser: self })
     }
 
@@ -167,12 +360,16 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        E::serialize_len(self, variant_index as usize)?;
+        self.serialize_variant_index(variant_index)?;
         Ok(Compound { ser: self })
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        unimplemented!()
+        let len = len.ok_or_else(|| {
+            Error::Message("Tried to serialize a map without a known length.".to_owned())
+        })?;
+        E::serialize_seq_len(self, len)?;
+        Ok(Compound { ser: self })
     }
 
     fn serialize_struct(
@@ -190,15 +387,41 @@ where
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        E::serialize_len(self, variant_index as usize)?;
+        self.serialize_variant_index(variant_index)?;
         Ok(Compound { ser: self })
     }
 
-    fn collect_str<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn collect_map<K, V, I>(self, iter: I) -> Result<Self::Ok, Self::Error>
     where
-        T: Display,
+        K: Serialize,
+        V: Serialize,
+        I: IntoIterator<Item = (K, V)>,
     {
-        unimplemented!()
+        let iter = iter.into_iter();
+        if !self.canonical_maps {
+            let len = match iter.size_hint() {
+                (lower, Some(upper)) if lower == upper => Some(lower),
+                _ => None,
+            };
+            let mut map = self.serialize_map(len)?;
+            for (key, value) in iter {
+                map.serialize_entry(&key, &value)?;
+            }
+            return SerializeMap::end(map);
+        }
+        let mut entries = iter
+            .map(|(key, value)| {
+                let mut ordering = BitSerializer::<O, S, E>::new().with_canonical_maps();
+                key.serialize(&mut ordering)?;
+                Ok((ordering.vec, key, value))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        entries.sort_by(|a, b| a.0.as_bitslice().cmp(b.0.as_bitslice()));
+        let mut map = self.serialize_map(Some(entries.len()))?;
+        for (_, key, value) in entries {
+            map.serialize_entry(&key, &value)?;
+        }
+        SerializeMap::end(map)
     }
 }
 
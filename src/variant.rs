@@ -0,0 +1,593 @@
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+use serde::de::value::{U32Deserializer, U64Deserializer};
+use serde::de::{DeserializeSeed, EnumAccess, IntoDeserializer, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{Impossible, SerializeTuple, SerializeTupleStruct};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::container::{deserialize_bits, serialize_bits};
+
+/// The tuple struct name `CompactEnum` is serialized under, its length carries the variant count.
+pub(crate) const COMPACT_ENUM: &str = "\0bitserde::CompactEnum";
+
+/// The tuple struct name the enum inside `Explicit` is serialized under, its length carries the
+/// variant index so the enum itself does not write one.
+pub(crate) const KNOWN_VARIANT: &str = "\0bitserde::KnownVariant";
+
+/// The number of bits needed to tell `variants` variants apart.
+#[inline]
+pub(crate) fn variant_bits(variants: usize) -> usize {
+    (usize::BITS - variants.saturating_sub(1).leading_zeros()) as usize
+}
+
+/// Tells `CompactEnum` how many variants an enum has.
+pub trait Variants {
+    /// The number of variants, at least one.
+    const COUNT: usize;
+}
+
+/// Writes the discriminant of the enum `T` in `ceil(log2(T::COUNT))` bits instead of through
+/// `serialize_variant_index`, indices past the last variant are rejected with `Error::InvalidVariant`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactEnum<T>(pub T);
+
+impl<T: Serialize + Variants> Serialize for CompactEnum<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple_struct(COMPACT_ENUM, T::COUNT)?;
+        tuple.serialize_field(&self.0)?;
+        tuple.end()
+    }
+}
+
+/// Visits the single element of a wrapping tuple struct.
+struct WrappedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for WrappedVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a wrapped enum")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        seq.next_element()?
+            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Variants> Deserialize<'de> for CompactEnum<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_tuple_struct(COMPACT_ENUM, T::COUNT, WrappedVisitor(PhantomData))
+            .map(CompactEnum)
+    }
+}
+
+/// Stops serialization at the first enum variant, reporting its index and, for a newtype variant
+/// wrapping an unsigned integer, that integer.
+#[derive(Debug)]
+enum IndexProbe {
+    Variant(u32, Option<u64>),
+    NotAnEnum,
+}
+
+impl Display for IndexProbe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for IndexProbe {}
+
+impl serde::ser::Error for IndexProbe {
+    fn custom<T: Display>(_msg: T) -> Self {
+        IndexProbe::NotAnEnum
+    }
+}
+
+macro_rules! reject_non_variants {
+    (@ok) => { Self::Ok };
+    (@ok $ret:ident) => { Self::$ret };
+    ($($method:ident($($arg:ident: $type:ty),*) $(-> $ret:ident)?),*) => {
+        $(
+            fn $method(
+                self,
+                $($arg: $type),*
+            ) -> Result<reject_non_variants!(@ok $($ret)?), IndexProbe> {
+                Err(IndexProbe::NotAnEnum)
+            }
+        )*
+    };
+}
+
+impl Serializer for IndexProbe {
+    type Ok = ();
+    type Error = IndexProbe;
+    type SerializeSeq = Impossible<(), IndexProbe>;
+    type SerializeTuple = Impossible<(), IndexProbe>;
+    type SerializeTupleStruct = Impossible<(), IndexProbe>;
+    type SerializeTupleVariant = Impossible<(), IndexProbe>;
+    type SerializeMap = Impossible<(), IndexProbe>;
+    type SerializeStruct = Impossible<(), IndexProbe>;
+    type SerializeStructVariant = Impossible<(), IndexProbe>;
+
+    reject_non_variants![
+        serialize_bool(_v: bool),
+        serialize_i8(_v: i8),
+        serialize_i16(_v: i16),
+        serialize_i32(_v: i32),
+        serialize_i64(_v: i64),
+        serialize_u8(_v: u8),
+        serialize_u16(_v: u16),
+        serialize_u32(_v: u32),
+        serialize_u64(_v: u64),
+        serialize_f32(_v: f32),
+        serialize_f64(_v: f64),
+        serialize_char(_v: char),
+        serialize_str(_v: &str),
+        serialize_bytes(_v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(_name: &'static str)
+    ];
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), IndexProbe> {
+        Err(IndexProbe::Variant(variant_index, None))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<(), IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), IndexProbe> {
+        Err(IndexProbe::Variant(
+            variant_index,
+            value.serialize(CodeProbe).ok(),
+        ))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, IndexProbe> {
+        Err(IndexProbe::Variant(variant_index, None))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, IndexProbe> {
+        Err(IndexProbe::Variant(variant_index, None))
+    }
+}
+
+/// Takes the payload of a newtype variant if it is an unsigned integer.
+struct CodeProbe;
+
+impl Serializer for CodeProbe {
+    type Ok = u64;
+    type Error = IndexProbe;
+    type SerializeSeq = Impossible<u64, IndexProbe>;
+    type SerializeTuple = Impossible<u64, IndexProbe>;
+    type SerializeTupleStruct = Impossible<u64, IndexProbe>;
+    type SerializeTupleVariant = Impossible<u64, IndexProbe>;
+    type SerializeMap = Impossible<u64, IndexProbe>;
+    type SerializeStruct = Impossible<u64, IndexProbe>;
+    type SerializeStructVariant = Impossible<u64, IndexProbe>;
+
+    fn serialize_u8(self, v: u8) -> Result<u64, IndexProbe> {
+        Ok(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<u64, IndexProbe> {
+        Ok(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<u64, IndexProbe> {
+        Ok(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<u64, IndexProbe> {
+        Ok(v)
+    }
+
+    reject_non_variants![
+        serialize_bool(_v: bool),
+        serialize_i8(_v: i8),
+        serialize_i16(_v: i16),
+        serialize_i32(_v: i32),
+        serialize_i64(_v: i64),
+        serialize_f32(_v: f32),
+        serialize_f64(_v: f64),
+        serialize_char(_v: char),
+        serialize_str(_v: &str),
+        serialize_bytes(_v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(_name: &'static str),
+        serialize_unit_variant(_name: &'static str, _index: u32, _variant: &'static str),
+        serialize_seq(_len: Option<usize>) -> SerializeSeq,
+        serialize_tuple(_len: usize) -> SerializeTuple,
+        serialize_tuple_struct(_name: &'static str, _len: usize) -> SerializeTupleStruct,
+        serialize_tuple_variant(
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize
+        ) -> SerializeTupleVariant,
+        serialize_map(_len: Option<usize>) -> SerializeMap,
+        serialize_struct(_name: &'static str, _len: usize) -> SerializeStruct,
+        serialize_struct_variant(
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize
+        ) -> SerializeStructVariant
+    ];
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<u64, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<u64, IndexProbe> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<u64, IndexProbe> {
+        Err(IndexProbe::NotAnEnum)
+    }
+}
+
+/// Looks up the index of the variant `value` holds through its `Serialize` implementation, along
+/// with its payload if that is an unsigned integer.
+fn variant_index<T: Serialize>(value: &T) -> Option<(u32, Option<u64>)> {
+    match value.serialize(IndexProbe::NotAnEnum) {
+        Err(IndexProbe::Variant(index, code)) => Some((index, code)),
+        _ => None,
+    }
+}
+
+/// Assigns explicit wire discriminants to the variants of an enum, used through `Explicit`.
+pub trait Discriminants {
+    /// The number of bits every discriminant is written in, at most 64.
+    const BITS: usize;
+
+    /// The discriminant of every variant, in declaration order. Each variant needs exactly one and
+    /// no two may be equal, duplicates are rejected at compile time.
+    const VALUES: &'static [u64];
+
+    /// The index of the variant unknown discriminants decode to, they are rejected if `None`.
+    ///
+    /// The fallback is a unit variant or a newtype variant around an unsigned integer, which
+    /// receives the unknown discriminant and writes it back. A unit fallback loses the
+    /// discriminant and is written as its own entry in `VALUES`.
+    const FALLBACK: Option<u32> = None;
+}
+
+/// Checks that `values` holds distinct discriminants that fit in `bits` bits and that `fallback`
+/// names one of them.
+const fn check_discriminants(bits: usize, values: &[u64], fallback: Option<u32>) {
+    assert!(bits <= 64, "Discriminants are at most 64 bits.");
+    let mut index = 0;
+    while index < values.len() {
+        assert!(
+            bits == 64 || values[index] >> bits == 0,
+            "A discriminant does not fit in BITS bits."
+        );
+        let mut other = 0;
+        while other < index {
+            assert!(
+                values[other] != values[index],
+                "Two variants share a discriminant."
+            );
+            other += 1;
+        }
+        index += 1;
+    }
+    if let Some(fallback) = fallback {
+        assert!(
+            (fallback as usize) < values.len(),
+            "The fallback variant has no discriminant."
+        );
+    }
+}
+
+/// Writes the enum `T` as its `Discriminants` value in `T::BITS` bits instead of its variant index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Explicit<T>(pub T);
+
+impl<T: Discriminants> Explicit<T> {
+    const VALID: () = check_discriminants(T::BITS, T::VALUES, T::FALLBACK);
+}
+
+/// The discriminant and the width it is written in.
+struct Code(u64, usize);
+
+impl Serialize for Code {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bits(self.0, self.1, serializer)
+    }
+}
+
+/// Reads a discriminant of the given width.
+struct CodeBits(usize);
+
+impl<'de> DeserializeSeed<'de> for CodeBits {
+    type Value = u64;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_bits(self.0, deserializer)
+    }
+}
+
+/// The enum inside `Explicit` along with the variant index its discriminant stands for.
+struct KnownVariant<T>(usize, T);
+
+impl<T: Serialize> Serialize for KnownVariant<&T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple_struct(KNOWN_VARIANT, self.0)?;
+        tuple.serialize_field(self.1)?;
+        tuple.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for KnownVariant<PhantomData<T>> {
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple_struct(KNOWN_VARIANT, self.0, WrappedVisitor(PhantomData))
+    }
+}
+
+impl<T: Serialize + Discriminants> Serialize for Explicit<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let () = Self::VALID;
+        let (index, code) = variant_index(&self.0).ok_or_else(|| {
+            serde::ser::Error::custom("Tried to serialize an Explicit that does not wrap an enum.")
+        })?;
+        if index as usize >= T::VALUES.len() {
+            return Err(serde::ser::Error::custom(format!(
+                "Variant {} has no discriminant, there are {}.",
+                index,
+                T::VALUES.len()
+            )));
+        }
+        let fallback = T::FALLBACK == Some(index);
+        let index = index as usize;
+        let value = match code {
+            Some(code) if fallback && T::VALUES.contains(&code) => {
+                return Err(serde::ser::Error::custom(format!(
+                    "The fallback variant cannot hold the known discriminant {:#x}.",
+                    code
+                )))
+            }
+            Some(code) if fallback => code,
+            _ => T::VALUES[index],
+        };
+        if T::BITS < 64 && value >> T::BITS != 0 {
+            return Err(serde::ser::Error::custom(format!(
+                "Discriminant {:#x} does not fit in {} bits.",
+                value,
+                T::BITS
+            )));
+        }
+        if fallback {
+            let mut tuple = serializer.serialize_tuple(1)?;
+            tuple.serialize_element(&Code(value, T::BITS))?;
+            return tuple.end();
+        }
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&Code(value, T::BITS))?;
+        tuple.serialize_element(&KnownVariant(index, &self.0))?;
+        tuple.end()
+    }
+}
+
+/// Hands the fallback variant of an enum the unknown discriminant as its payload.
+struct Fallback<E> {
+    index: u32,
+    code: u64,
+    marker: PhantomData<E>,
+}
+
+impl<'de, E: serde::de::Error> Deserializer<'de> for Fallback<E> {
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+impl<'de, E: serde::de::Error> EnumAccess<'de> for Fallback<E> {
+    type Error = E;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), E>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let index: U32Deserializer<E> = self.index.into_deserializer();
+        seed.deserialize(index).map(|variant| (variant, self))
+    }
+}
+
+impl<'de, E: serde::de::Error> VariantAccess<'de> for Fallback<E> {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, E>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let code: U64Deserializer<E> = self.code.into_deserializer();
+        seed.deserialize(code)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        Err(E::custom(
+            "The fallback variant must be a unit variant or wrap an unsigned integer.",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        Err(E::custom(
+            "The fallback variant must be a unit variant or wrap an unsigned integer.",
+        ))
+    }
+}
+
+struct ExplicitVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Discriminants> Visitor<'de> for ExplicitVisitor<T> {
+    type Value = Explicit<T>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "a {} bit discriminant followed by an enum",
+            T::BITS
+        )
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let value = seq
+            .next_element_seed(CodeBits(T::BITS))?
+            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let () = Explicit::<T>::VALID;
+        let index = match T::VALUES.iter().position(|known| *known == value) {
+            Some(index) => index,
+            None => {
+                let index = T::FALLBACK.ok_or_else(|| {
+                    serde::de::Error::custom(format!("Unknown discriminant {:#x}.", value))
+                })?;
+                return T::deserialize(Fallback {
+                    index,
+                    code: value,
+                    marker: PhantomData,
+                })
+                .map(Explicit);
+            }
+        };
+        seq.next_element_seed(KnownVariant(index, PhantomData))?
+            .map(Explicit)
+            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Discriminants> Deserialize<'de> for Explicit<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, ExplicitVisitor(PhantomData))
+    }
+}